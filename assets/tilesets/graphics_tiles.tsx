@@ -3,237 +3,376 @@
  <grid orientation="orthogonal" width="1" height="1"/>
  <tile id="0" type="GraphicsTileBundle">
   <properties>
-   <property name="animating">{
-  &quot;on_anim&quot;: [
-    {&quot;id&quot;:0, &quot;dur&quot;:125},
-    {&quot;id&quot;:1, &quot;dur&quot;:125},
-    {&quot;id&quot;:2, &quot;dur&quot;:125},
-    {&quot;id&quot;:3, &quot;dur&quot;:125}
-  ]
-}</property>
+   <property name="animating" type="class" propertytype="TileAnimations">
+    <properties>
+     <property name="on_anim" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="125"/>
+         <property name="id" type="int" value="0"/>
+        </properties>
+       </property>
+       <property name="1" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="125"/>
+         <property name="id" type="int" value="1"/>
+        </properties>
+       </property>
+       <property name="2" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="125"/>
+         <property name="id" type="int" value="2"/>
+        </properties>
+       </property>
+       <property name="3" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="125"/>
+         <property name="id" type="int" value="3"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+    </properties>
+   </property>
   </properties>
   <image width="32" height="32" source="../tiles/conveyor1.png"/>
  </tile>
  <tile id="1" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/conveyor2.png"/>
  </tile>
  <tile id="2" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/conveyor3.png"/>
  </tile>
  <tile id="3" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/conveyor4.png"/>
  </tile>
  <tile id="4" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/exit.png"/>
  </tile>
  <tile id="5" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/floor1.png"/>
  </tile>
  <tile id="6" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/floor2.png"/>
  </tile>
  <tile id="7" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/floor3.png"/>
  </tile>
  <tile id="8" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/floor4.png"/>
  </tile>
  <tile id="9" type="GraphicsTileBundle">
   <properties>
-   <property name="animating">{
-  &quot;on_transition&quot;: [
-    {&quot;id&quot;:9, &quot;dur&quot;:100},
-    {&quot;id&quot;:10, &quot;dur&quot;:100},
-    {&quot;id&quot;:11, &quot;dur&quot;:100},
-    {&quot;id&quot;:12, &quot;dur&quot;:100}
-  ],
-  &quot;off_transition&quot;: [
-    {&quot;id&quot;:12, &quot;dur&quot;:100},
-    {&quot;id&quot;:11, &quot;dur&quot;:100},
-    {&quot;id&quot;:10, &quot;dur&quot;:100},
-    {&quot;id&quot;:9, &quot;dur&quot;:100}
-  ],
-  &quot;on_anim&quot;: [
-    {&quot;id&quot;:12, &quot;dur&quot;:125},
-    {&quot;id&quot;:13, &quot;dur&quot;:50},
-    {&quot;id&quot;:14, &quot;dur&quot;:200},
-    {&quot;id&quot;:15, &quot;dur&quot;:50},
-    {&quot;id&quot;:16, &quot;dur&quot;:125}
-  ],
-  &quot;off_anim&quot;: [
-    {&quot;id&quot;:9, &quot;dur&quot;:125}
-  ]
-}</property>
+   <property name="animating" type="class" propertytype="TileAnimations">
+    <properties>
+     <property name="on_transition" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="9"/>
+        </properties>
+       </property>
+       <property name="1" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="10"/>
+        </properties>
+       </property>
+       <property name="2" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="11"/>
+        </properties>
+       </property>
+       <property name="3" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="12"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+     <property name="off_transition" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="12"/>
+        </properties>
+       </property>
+       <property name="1" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="11"/>
+        </properties>
+       </property>
+       <property name="2" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="10"/>
+        </properties>
+       </property>
+       <property name="3" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="9"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+     <property name="on_anim" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="125"/>
+         <property name="id" type="int" value="12"/>
+        </properties>
+       </property>
+       <property name="1" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="50"/>
+         <property name="id" type="int" value="13"/>
+        </properties>
+       </property>
+       <property name="2" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="200"/>
+         <property name="id" type="int" value="14"/>
+        </properties>
+       </property>
+       <property name="3" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="50"/>
+         <property name="id" type="int" value="15"/>
+        </properties>
+       </property>
+       <property name="4" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="125"/>
+         <property name="id" type="int" value="16"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+     <property name="off_anim" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="125"/>
+         <property name="id" type="int" value="9"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+    </properties>
+   </property>
   </properties>
   <image width="32" height="32" source="../tiles/fry0.png"/>
  </tile>
  <tile id="10" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/fry1.png"/>
  </tile>
  <tile id="11" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/fry2.png"/>
  </tile>
  <tile id="12" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/fry3.png"/>
  </tile>
  <tile id="13" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/fry4.png"/>
  </tile>
  <tile id="14" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/fry5.png"/>
  </tile>
  <tile id="15" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/fry6.png"/>
  </tile>
  <tile id="16" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/fry7.png"/>
  </tile>
  <tile id="17" type="GraphicsTileBundle">
   <properties>
-   <property name="animating">{
-  &quot;on_anim&quot;: [
-    {&quot;id&quot;:17, &quot;dur&quot;:100},
-    {&quot;id&quot;:18, &quot;dur&quot;:100},
-    {&quot;id&quot;:19, &quot;dur&quot;:100},
-    {&quot;id&quot;:20, &quot;dur&quot;:100}
-  ]
-}</property>
+   <property name="animating" type="class" propertytype="TileAnimations">
+    <properties>
+     <property name="on_anim" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="17"/>
+        </properties>
+       </property>
+       <property name="1" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="18"/>
+        </properties>
+       </property>
+       <property name="2" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="19"/>
+        </properties>
+       </property>
+       <property name="3" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="20"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+    </properties>
+   </property>
   </properties>
   <image width="32" height="32" source="../tiles/spin0.png"/>
  </tile>
  <tile id="18" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/spin1.png"/>
  </tile>
  <tile id="19" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/spin2.png"/>
  </tile>
  <tile id="20" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/spin3.png"/>
  </tile>
  <tile id="21" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/screen_empty.png"/>
  </tile>
  <tile id="22" type="GraphicsTileBundle">
   <properties>
-   <property name="animating">{
-  &quot;on_transition&quot;: [
-    {&quot;id&quot;:21, &quot;dur&quot;:200},
-    {&quot;id&quot;:22, &quot;dur&quot;:50}
-  ],
-  &quot;off_transition&quot;: [
-    {&quot;id&quot;:21, &quot;dur&quot;:200},
-    {&quot;id&quot;:24, &quot;dur&quot;:50}
-  ],
-  &quot;on_anim&quot;: [
-    {&quot;id&quot;:22, &quot;dur&quot;:125}
-  ],
-  &quot;off_anim&quot;: [
-    {&quot;id&quot;:24, &quot;dur&quot;:100}
-  ]
-}</property>
+   <property name="animating" type="class" propertytype="TileAnimations">
+    <properties>
+     <property name="on_transition" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="200"/>
+         <property name="id" type="int" value="21"/>
+        </properties>
+       </property>
+       <property name="1" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="50"/>
+         <property name="id" type="int" value="22"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+     <property name="off_transition" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="200"/>
+         <property name="id" type="int" value="21"/>
+        </properties>
+       </property>
+       <property name="1" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="50"/>
+         <property name="id" type="int" value="24"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+     <property name="on_anim" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="125"/>
+         <property name="id" type="int" value="22"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+     <property name="off_anim" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="24"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+    </properties>
+   </property>
   </properties>
   <image width="32" height="32" source="../tiles/screen_even.png"/>
  </tile>
  <tile id="23" type="GraphicsTileBundle">
   <properties>
-   <property name="animating">{
-  &quot;on_transition&quot;: [
-    {&quot;id&quot;:21, &quot;dur&quot;:200},
-    {&quot;id&quot;:23, &quot;dur&quot;:50}
-  ],
-  &quot;off_transition&quot;: [
-    {&quot;id&quot;:21, &quot;dur&quot;:200},
-    {&quot;id&quot;:25, &quot;dur&quot;:50}
-  ],
-  &quot;on_anim&quot;: [
-    {&quot;id&quot;:23, &quot;dur&quot;:125}
-  ],
-  &quot;off_anim&quot;: [
-    {&quot;id&quot;:25, &quot;dur&quot;:100}
-  ]
-}</property>
+   <property name="animating" type="class" propertytype="TileAnimations">
+    <properties>
+     <property name="on_transition" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="200"/>
+         <property name="id" type="int" value="21"/>
+        </properties>
+       </property>
+       <property name="1" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="50"/>
+         <property name="id" type="int" value="23"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+     <property name="off_transition" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="200"/>
+         <property name="id" type="int" value="21"/>
+        </properties>
+       </property>
+       <property name="1" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="50"/>
+         <property name="id" type="int" value="25"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+     <property name="on_anim" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="125"/>
+         <property name="id" type="int" value="23"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+     <property name="off_anim" type="class" propertytype="TileAnimation">
+      <properties>
+       <property name="0" type="class" propertytype="TileAnimationFrame">
+        <properties>
+         <property name="dur" type="int" value="100"/>
+         <property name="id" type="int" value="25"/>
+        </properties>
+       </property>
+      </properties>
+     </property>
+    </properties>
+   </property>
   </properties>
   <image width="32" height="32" source="../tiles/screen_odd.png"/>
  </tile>
  <tile id="24" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/screen_even_off.png"/>
  </tile>
  <tile id="25" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/screen_odd_off.png"/>
  </tile>
  <tile id="26" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/hint1.png"/>
  </tile>
  <tile id="27" type="GraphicsTileBundle">
-  <properties>
-   <property name="animating" value="null"/>
-  </properties>
   <image width="32" height="32" source="../tiles/hint2.png"/>
  </tile>
 </tileset>
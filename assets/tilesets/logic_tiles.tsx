@@ -3,43 +3,47 @@
  <grid orientation="orthogonal" width="1" height="1"/>
  <tile id="0" type="LogicTileBundle">
   <properties>
-   <property name="ty" propertytype="LevelTileType" value="Conveyor"/>
+   <property name="ty" propertytype="LogicKind" value="Conveyor"/>
   </properties>
   <image width="32" height="32" source="../tiles/logic_conveyor.png"/>
  </tile>
  <tile id="1" type="LogicTileBundle">
   <properties>
-   <property name="ty" propertytype="LevelTileType" value="Exit"/>
+   <property name="ty" propertytype="LogicKind" value="Exit"/>
   </properties>
   <image width="32" height="32" source="../tiles/logic_exit.png"/>
  </tile>
  <tile id="2" type="LogicTileBundle">
   <properties>
-   <property name="ty" propertytype="LevelTileType" value="Floor"/>
+   <property name="ty" propertytype="LogicKind" value="Floor"/>
   </properties>
   <image width="32" height="32" source="../tiles/logic_floor.png"/>
  </tile>
  <tile id="3" type="LogicTileBundle">
   <properties>
-   <property name="ty" propertytype="LevelTileType" value="Frier"/>
+   <property name="ty" propertytype="LogicKind" value="Frier"/>
   </properties>
   <image width="32" height="32" source="../tiles/logic_kill.png"/>
  </tile>
  <tile id="4" type="LogicTileBundle">
   <properties>
-   <property name="ty" propertytype="LevelTileType" value="Spinner"/>
+   <property name="ty" propertytype="LogicKind" value="Spinner"/>
   </properties>
   <image width="32" height="32" source="../tiles/logic_spin.png"/>
  </tile>
  <tile id="5" type="LogicTileBundle">
   <properties>
-   <property name="ty" propertytype="LevelTileType" value="Start"/>
+   <property name="ty" propertytype="LogicKind" value="Start"/>
   </properties>
   <image width="32" height="32" source="../tiles/logic_start.png"/>
  </tile>
  <tile id="6" type="LogicTileBundle">
   <properties>
-   <property name="ty" propertytype="LevelTileType" value="OnceButton"/>
+   <property name="ty" type="class" propertytype="OnceButton">
+    <properties>
+     <property name="0" type="int" value="1"/>
+    </properties>
+   </property>
   </properties>
   <image width="32" height="32" source="../tiles/logic_once_button.png"/>
  </tile>
@@ -0,0 +1,162 @@
+//! Module which houses the registry of the tile builders. The plugins
+//! register a builder for each family of tiles they add, and the level
+//! spawning code picks them by the tileset without knowing about them.
+
+use std::sync::Arc;
+
+use anyhow::bail;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use tiled::Tileset;
+
+use crate::{BasicDeserBuilder, CallbackSelector, ReflectTileBuilder, TileBuilder};
+
+/// Decides which tilesets a registered builder gets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TilesetMatch {
+    /// The tileset with this name
+    Name(String),
+    /// Any tileset with a tile of this class
+    Class(String),
+}
+
+impl TilesetMatch {
+    pub fn matches(&self, tileset: &Tileset) -> bool {
+        match self {
+            TilesetMatch::Name(name) => tileset.name == *name,
+            TilesetMatch::Class(class) => tileset.tiles()
+                .any(|(_, tile)| tile.user_type.as_deref() == Some(class.as_str())),
+        }
+    }
+}
+
+impl From<&str> for TilesetMatch {
+    fn from(name: &str) -> Self {
+        TilesetMatch::Name(name.to_owned())
+    }
+}
+
+type BuilderFactory = Box<dyn Fn() -> Box<dyn TileBuilder> + Send + Sync>;
+
+/// The tile builders registered by the plugins. Each map gets fresh
+/// builders, made by [TileBuilderRegistry::selector].
+#[derive(Resource, Default)]
+pub struct TileBuilderRegistry {
+    entries: Vec<(TilesetMatch, BuilderFactory)>,
+}
+
+impl TileBuilderRegistry {
+    /// Registers a builder. The rules are checked in the order of the
+    /// registration, the first one matching the tileset wins.
+    pub fn add(
+        &mut self,
+        rule: impl Into<TilesetMatch>,
+        factory: impl Fn() -> Box<dyn TileBuilder> + Send + Sync + 'static,
+    ) {
+        self.entries.push((rule.into(), Box::new(factory)));
+    }
+
+    /// Makes the builders for parsing a map. `fallback` gets the tilesets
+    /// no rule matches. Without it, such tilesets fail the parsing.
+    pub fn selector<'a>(&'a self, fallback: Option<&'a mut dyn TileBuilder>) -> RegistrySelector<'a> {
+        RegistrySelector {
+            builders: self.entries.iter()
+                .map(|(rule, factory)| (rule, factory()))
+                .collect(),
+            fallback,
+        }
+    }
+}
+
+/// Picks the builders made from a [TileBuilderRegistry].
+pub struct RegistrySelector<'a> {
+    builders: Vec<(&'a TilesetMatch, Box<dyn TileBuilder>)>,
+    fallback: Option<&'a mut dyn TileBuilder>,
+}
+
+impl<'a> CallbackSelector for RegistrySelector<'a> {
+    fn select(&mut self, tileset: &Tileset) -> anyhow::Result<&mut dyn TileBuilder> {
+        let builder = self.builders.iter_mut()
+            .find(|(rule, _)| rule.matches(tileset));
+
+        match (builder, &mut self.fallback) {
+            (Some((_, builder)), _) => Ok(builder.as_mut()),
+            (None, Some(fallback)) => Ok(&mut **fallback),
+            (None, None) => bail!("No tile builder is registered for tileset {:?}", tileset.name),
+        }
+    }
+}
+
+pub trait TileBuilderAppExt {
+    /// Registers a [BasicDeserBuilder] for the tiles of type `T`. The
+    /// `finish_layer` callback gets the commands of each finished tilemap.
+    fn register_tileset_builder<T>(
+        &mut self,
+        rule: impl Into<TilesetMatch>,
+        finish_layer: impl Fn(&mut EntityCommands) + Send + Sync + 'static,
+    ) -> &mut Self
+    where
+        T: DeserializeOwned + Bundle + Clone;
+
+    /// Registers a [ReflectTileBuilder], which turns the class of each
+    /// tile into a registered component.
+    fn register_reflect_tileset_builder(
+        &mut self,
+        rule: impl Into<TilesetMatch>,
+        finish_layer: impl Fn(&mut EntityCommands) + Send + Sync + 'static,
+    ) -> &mut Self;
+
+    /// Registers a custom builder. `factory` makes one for each map.
+    fn register_tileset_builder_with(
+        &mut self,
+        rule: impl Into<TilesetMatch>,
+        factory: impl Fn() -> Box<dyn TileBuilder> + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl TileBuilderAppExt for App {
+    fn register_tileset_builder<T>(
+        &mut self,
+        rule: impl Into<TilesetMatch>,
+        finish_layer: impl Fn(&mut EntityCommands) + Send + Sync + 'static,
+    ) -> &mut Self
+    where
+        T: DeserializeOwned + Bundle + Clone,
+    {
+        let finish_layer = Arc::new(finish_layer);
+
+        self.register_tileset_builder_with(rule, move || {
+            let finish_layer = finish_layer.clone();
+            Box::new(BasicDeserBuilder::<T, _>::new(move |cmds: &mut EntityCommands| finish_layer(cmds)))
+        })
+    }
+
+    fn register_reflect_tileset_builder(
+        &mut self,
+        rule: impl Into<TilesetMatch>,
+        finish_layer: impl Fn(&mut EntityCommands) + Send + Sync + 'static,
+    ) -> &mut Self {
+        let registry = self.world.resource::<AppTypeRegistry>().clone();
+        let finish_layer = Arc::new(finish_layer);
+
+        self.register_tileset_builder_with(rule, move || {
+            let finish_layer = finish_layer.clone();
+            Box::new(ReflectTileBuilder::new(
+                registry.clone(),
+                move |cmds: &mut EntityCommands| finish_layer(cmds),
+            ))
+        })
+    }
+
+    fn register_tileset_builder_with(
+        &mut self,
+        rule: impl Into<TilesetMatch>,
+        factory: impl Fn() -> Box<dyn TileBuilder> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.init_resource::<TileBuilderRegistry>();
+        self.world.resource_mut::<TileBuilderRegistry>().add(rule, factory);
+
+        self
+    }
+}
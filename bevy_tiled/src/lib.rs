@@ -5,11 +5,17 @@ pub extern crate tiled;
 
 pub mod tiled_ext;
 pub mod tiled_map_asset;
+pub mod tiled_tileset_asset;
 pub mod map_scheme;
+pub mod builder_registry;
+pub mod reflect_builder;
 
 pub use tiled_ext::*;
 pub use tiled_map_asset::*;
+pub use tiled_tileset_asset::*;
 pub use map_scheme::*;
+pub use builder_registry::*;
+pub use reflect_builder::*;
 
 use bevy::prelude::*;
 
@@ -18,8 +24,19 @@ pub struct TiledPlugin;
 
 impl Plugin for TiledPlugin {
     fn build(&self, app: &mut App) {
+        let cache = TilesetCache::default();
+
         app
+            .init_resource::<TileBuilderRegistry>()
+            .insert_resource(cache.clone())
             .add_asset::<TiledMap>()
-            .add_asset_loader(TiledMapLoader);
+            .add_asset::<TiledTilesetAsset>()
+            .add_asset_loader(TiledMapLoader::new(cache.clone()))
+            .add_asset_loader(TiledTilesetLoader::new(cache))
+            .add_system(evict_removed_tilesets)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                apply_parallax.before(bevy::transform::TransformSystem::TransformPropagate),
+            );
     }
 } 
@@ -1,12 +1,17 @@
 use anyhow::{anyhow, bail, ensure, Context};
-use bevy_ecs_tilemap::{tiles::{TileBundle, TilePos, TileTextureIndex, TileStorage}, prelude::{TilemapId, TilemapSize, TilemapTexture, TilemapType, TilemapTileSize, TilemapGridSize}, TilemapBundle};
+use bevy_ecs_tilemap::{tiles::{TileBundle, TilePos, TileTextureIndex, TileStorage}, prelude::{TilemapId, TilemapTexture, TilemapType, TilemapTileSize, TilemapGridSize}, TilemapBundle};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 
-use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy::{ecs::system::EntityCommands, prelude::*, sprite::Anchor};
 use serde::de::DeserializeOwned;
-use tiled::{Tileset, Map, Layer, LayerType, TileLayer, FiniteTileLayer, LayerTileData};
+use serde::Deserialize;
+use tiled::{Map, Tileset, Layer, LayerType, TileLayer, LayerTile, LayerTileData, ImageLayer, ObjectLayer, ObjectData, ObjectShape};
 
-use crate::{TileExt, TilesetIndexing, TiledLayerTileExt};
+use bevy_ecs_tilemap_cpu_anim::CPUAnimated;
+
+use crate::{LayerImages, TileBounds, TileLayerExt, TilePropertyDes, TilePropertyDeserError, TiledMap, TilesetAnimations, TilesetData, TilesetIndexing, TiledLayerTileExt};
 
 /// An interface for the tilemap parser to call as it visits different
 /// parts of the tilemap asset.
@@ -16,8 +21,7 @@ pub trait TileBuilder {
     fn process_tileset(
         &mut self,
         set_id: usize,
-        tileset: &Tileset,
-        indexing: &TilesetIndexing
+        tileset: &TilesetData,
     ) -> anyhow::Result<()>;
 
     /// Gets called for each tile on each layer.
@@ -36,13 +40,94 @@ pub trait TileBuilder {
     ) -> anyhow::Result<()>;
 }
 
+/// An object from an object layer, as the [ObjectBuilder] sees it.
+pub struct MapObject<'a> {
+    pub data: &'a ObjectData,
+    /// The position of the top left corner of the object in tile
+    /// coordinates. Just like with the tiles, `y` grows upwards.
+    pub position: Vec2,
+    /// The directory of the map, which the file properties are relative to
+    pub base_dir: Option<&'a Path>,
+    map_size: Vec2,
+}
+
+impl<'a> MapObject<'a> {
+    /// Places the object onto the tilemaps spawned for `bounds`. `Tiled`
+    /// anchors the tile objects at their bottom left corner and the rest
+    /// at their top left one, so the tile objects get moved up.
+    pub fn new(data: &'a ObjectData, map: &'a Map, bounds: &TileBounds) -> Self {
+        let tile_size = Vec2::new(map.tile_width as f32, map.tile_height as f32);
+        let top = match (data.tile_data(), &data.shape) {
+            (Some(_), ObjectShape::Rect { height, .. }) => data.y - height,
+            _ => data.y,
+        };
+        let min = bounds.min.as_vec2();
+        let map_size = bounds.size.as_vec2();
+
+        MapObject {
+            data,
+            position: Vec2::new(
+                data.x / tile_size.x - min.x,
+                map_size.y - (top / tile_size.y - min.y),
+            ),
+            base_dir: map.source.parent(),
+            map_size,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        &self.data.name
+    }
+
+    /// The class of the object. Empty if the object has no class.
+    pub fn class(&self) -> &'a str {
+        &self.data.user_type
+    }
+
+    pub fn shape(&self) -> &'a ObjectShape {
+        &self.data.shape
+    }
+
+    /// The tile the top left corner of the object is in. The tiles are
+    /// half-open, just like in `Tiled`: a corner on the top edge of a tile
+    /// belongs to it and not to the tile above. `None` if the object is
+    /// outside of the map.
+    pub fn tile_pos(&self) -> Option<TilePos> {
+        let pos = Vec2::new(self.position.x.floor(), self.position.y.ceil() - 1.0);
+
+        (pos.cmpge(Vec2::ZERO).all() && pos.cmplt(self.map_size).all())
+            .then_some(TilePos { x: pos.x as u32, y: pos.y as u32 })
+    }
+
+    pub fn properties<D: Deserialize<'a>>(&self) -> Result<D, TilePropertyDeserError> {
+        D::deserialize(TilePropertyDes::from_object(self.data).with_base_dir(self.base_dir))
+    }
+}
+
+/// An interface for the map parser to call as it visits the objects of
+/// the object layers.
+pub trait ObjectBuilder {
+    /// Gets called for each object on each object layer.
+    fn build(
+        &mut self,
+        object: &MapObject,
+        cmds: &mut EntityCommands
+    ) -> anyhow::Result<()>;
+
+    /// Gets called by the parser after finishing a layer.
+    fn finish_layer(
+        &mut self,
+        cmds: &mut EntityCommands
+    ) -> anyhow::Result<()>;
+}
+
 pub struct BasicDeserBuilder<T, F = fn(&mut EntityCommands)>
 where
     T: DeserializeOwned + Bundle,
     F: Fn(&mut EntityCommands),
 {
     bundle_builder: F,
-    deserialized_props: HashMap<(usize, u32), T>,
+    deserialized_props: HashMap<usize, Arc<HashMap<u32, T>>>,
 }
 
 impl<T, F> BasicDeserBuilder<T, F>
@@ -75,15 +160,9 @@ where
     fn process_tileset(
         &mut self,
         set_id: usize,
-        tileset: &Tileset,
-        _indexing: &TilesetIndexing
+        tileset: &TilesetData,
     ) -> anyhow::Result<()> {
-        self.deserialized_props.reserve(tileset.tilecount as usize);
-
-        for (id, tile) in tileset.tiles() {
-            let props = tile.properties()?;
-            self.deserialized_props.insert((set_id, id), props);
-        }
+        self.deserialized_props.insert(set_id, tileset.properties()?);
 
         Ok(())
     }
@@ -94,7 +173,8 @@ where
         id: u32,
         cmds: &mut EntityCommands
     ) -> anyhow::Result<()> {
-        let props = self.deserialized_props.get(&(set_id, id))
+        let props = self.deserialized_props.get(&set_id)
+            .and_then(|props| props.get(&id))
             .ok_or_else(|| anyhow!("Tile {} didn't have any deserialized properties", id))?;
 
         cmds.insert(props.clone());
@@ -113,8 +193,10 @@ where
     }
 }
 
+/// Picks the [TileBuilder] for each of the tilesets. Fails if there's
+/// none for the tileset.
 pub trait CallbackSelector {
-    fn select(&mut self, tileset: &Tileset) -> &mut dyn TileBuilder;
+    fn select(&mut self, tileset: &Tileset) -> anyhow::Result<&mut dyn TileBuilder>;
 }
 
 pub struct SimpleCallbackSelector<'a, const N: usize> {
@@ -123,28 +205,80 @@ pub struct SimpleCallbackSelector<'a, const N: usize> {
 }
 
 impl<'a, const N: usize> CallbackSelector for SimpleCallbackSelector<'a, N> {
-    fn select(&mut self, tileset: &Tileset) -> &mut dyn TileBuilder {
-        self.pool[(self.picker)(&tileset.name)]
+    fn select(&mut self, tileset: &Tileset) -> anyhow::Result<&mut dyn TileBuilder> {
+        let idx = (self.picker)(&tileset.name);
+
+        match self.pool.get_mut(idx) {
+            Some(builder) => Ok(&mut **builder),
+            None => bail!("The picker chose builder {idx} for tileset {:?}, but there are only {N}", tileset.name),
+        }
+    }
+}
+
+/// The parallax factor of an image layer. The layer follows the camera,
+/// so that it scrolls `factor` times as fast as the rest of the map.
+#[derive(Clone, Copy, Debug, Component)]
+pub struct TiledParallax {
+    pub factor: Vec2,
+    /// The translation of the layer when the camera is at the origin
+    pub origin: Vec3,
+}
+
+pub fn apply_parallax(
+    cameras: Query<(&Camera, &Transform), (With<Camera2d>, Without<TiledParallax>)>,
+    mut layers: Query<(&TiledParallax, &mut Transform)>,
+) {
+    let camera_pos = match cameras.iter().find(|(camera, _)| camera.is_active) {
+        Some((_, transform)) => transform.translation.truncate(),
+        None => return,
+    };
+
+    for (parallax, mut transform) in layers.iter_mut() {
+        transform.translation = parallax.origin
+            + (camera_pos * (Vec2::ONE - parallax.factor)).extend(0.0);
     }
 }
 
 struct ParserState {
     layer_idx: u32,
+    bounds: TileBounds,
 }
 
 impl ParserState {
+    /// The layers are stacked along `z`, one unit apart
+    const MAX_LAYERS: u32 = 100;
+
     fn new() -> Self {
         Self {
             layer_idx: 0,
+            bounds: TileBounds::default(),
         }
     }
+
+    /// Moves on to the next tile or image layer.
+    fn next_layer(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.layer_idx < Self::MAX_LAYERS,
+            "The map has more than {} tile and image layers",
+            Self::MAX_LAYERS,
+        );
+        self.layer_idx += 1;
+
+        Ok(())
+    }
 }
 
+/// Spawns the layers of a map. Tiles with a native `Tiled` animation get
+/// a looping [CPUAnimated] before the [TileBuilder] gets to them. Image
+/// layers become sprites with the images from [LayerImages].
 pub struct MapParser<'w, 's, 'a, C> {
     state: ParserState,
     commands: &'a mut Commands<'w, 's>,
     callback_selector: C,
     tilemap_texture_data: &'a [(TilesetIndexing, TilemapTexture)],
+    animations: &'a TilesetAnimations,
+    layer_images: &'a LayerImages,
+    object_builder: Option<&'a mut dyn ObjectBuilder>,
 }
 
 impl<'w, 's, 'a, C: CallbackSelector> MapParser<'w, 's, 'a, C>
@@ -156,26 +290,42 @@ where
         commands: &'a mut Commands<'w, 's>,
         callback_selector: C,
         tilemap_texture_data: &'a [(TilesetIndexing, TilemapTexture)],
+        animations: &'a TilesetAnimations,
+        layer_images: &'a LayerImages,
     ) -> Self {
         Self {
             state: ParserState::new(),
             commands,
             callback_selector,
             tilemap_texture_data,
+            animations,
+            layer_images,
+            object_builder: None,
         }
     }
 
-    pub fn parse_map(&mut self, map: &Map) -> anyhow::Result<()> {
-        for (id, set) in map.tilesets().iter().enumerate() {
-            self.callback_selector.select(set).process_tileset(
-                id,
-                set,
-                &self.tilemap_texture_data[id].0
-            )?;
+    /// Sets the builder for the objects. Without one the object layers
+    /// can't be parsed.
+    pub fn with_object_builder(mut self, object_builder: &'a mut dyn ObjectBuilder) -> Self {
+        self.object_builder = Some(object_builder);
+        self
+    }
+
+    pub fn parse_map(&mut self, map: &TiledMap) -> anyhow::Result<()> {
+        for (id, set) in map.tilesets.iter().enumerate() {
+            self.callback_selector.select(&set.tileset)
+                .and_then(|builder| builder.process_tileset(id, set))
+                .with_context(|| format!("While processing tileset {:?}", set.tileset.name))?;
         }
 
+        let map = &map.map;
+        self.state.bounds = TileBounds::of_map(map);
+
         let callback_selector = &mut self.callback_selector;
         let tilemap_texture_data = &self.tilemap_texture_data;
+        let animations = self.animations;
+        let layer_images = self.layer_images;
+        let object_builder = &mut self.object_builder;
 
         let mut result = Ok(());
         self.commands.spawn((
@@ -195,6 +345,9 @@ where
                     &mut layer_cmds,
                     callback_selector,
                     tilemap_texture_data,
+                    animations,
+                    layer_images,
+                    object_builder,
                     layer,
                 );
                 if local_res.is_err() {
@@ -207,11 +360,15 @@ where
         result
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn parse_layer(
         state: &mut ParserState,
         layer_cmds: &mut EntityCommands,
         callback_selector: &mut C,
         tilemap_texture_data: &[(TilesetIndexing, TilemapTexture)],
+        animations: &TilesetAnimations,
+        layer_images: &LayerImages,
+        object_builder: &mut Option<&'a mut dyn ObjectBuilder>,
         layer: Layer
     ) -> anyhow::Result<()> {
         // Start visitting layers
@@ -235,6 +392,9 @@ where
                             &mut layer_cmds,
                             callback_selector,
                             tilemap_texture_data,
+                            animations,
+                            layer_images,
+                            object_builder,
                             layer,
                         )
                     });
@@ -246,52 +406,134 @@ where
 
                 result
             },
-            LayerType::ImageLayer(_) => bail!("Image layers are not supported"),
-            LayerType::ObjectLayer(_) => bail!("Objetc layers are not supported"),
-            LayerType::TileLayer(tile_layer) => match tile_layer {
-                TileLayer::Finite(tiles) => Self::parse_finite_tile_layer(
+            LayerType::ImageLayer(image_layer) => Self::parse_image_layer(
+                state,
+                layer_cmds,
+                layer_images,
+                layer,
+                image_layer,
+            ),
+            LayerType::ObjectLayer(objects) => match object_builder {
+                Some(object_builder) => Self::parse_object_layer(
                     state,
                     layer_cmds,
-                    callback_selector,
-                    tilemap_texture_data,
-                    tiles,
+                    &mut **object_builder,
+                    objects,
                 ),
-                TileLayer::Infinite(_) => bail!("Infinite tile layers are not supported")
-            }
+                None => bail!("Object layers can't be parsed without an object builder"),
+            },
+            LayerType::TileLayer(tiles) => Self::parse_tile_layer(
+                state,
+                layer_cmds,
+                callback_selector,
+                tilemap_texture_data,
+                animations,
+                tiles,
+            ),
         }
     }
 
-    fn parse_finite_tile_layer(
+    /// Spawns the tilemaps of a tile layer. A layer with tiles from one
+    /// tileset becomes a tilemap itself, otherwise it gets a child tilemap
+    /// per tileset. The chunks of an infinite layer all end up in the same
+    /// tilemap.
+    fn parse_tile_layer(
         state: &mut ParserState,
         layer_cmds: &mut EntityCommands,
         callback_selector: &mut C,
         tilemap_texture_data: &[(TilesetIndexing, TilemapTexture)],
-        tiles: FiniteTileLayer,
+        animations: &TilesetAnimations,
+        tiles: TileLayer,
     ) -> anyhow::Result<()> {
-        use itertools::iproduct;
+        let by_tileset = tiles.tiles_by_tileset();
+        ensure!(!by_tileset.is_empty(), "The layer uses no tileset");
+
+        let layer_transform = Transform::from_translation(Vec3::new(
+            0.0f32,
+            0.0f32,
+            state.layer_idx as f32,
+        ));
 
-        let (tileset_index, tileset) = ensure_unique_tileset(&tiles)?;
-        let provider = callback_selector.select(tileset);
-        let tilemap_size = TilemapSize { x: tiles.map().width, y: tiles.map().height };
+        if by_tileset.len() == 1 {
+            let (tileset_index, tiles) = by_tileset.into_iter().next().unwrap();
+
+            Self::spawn_tilemap(
+                state,
+                layer_cmds,
+                callback_selector,
+                tilemap_texture_data,
+                animations,
+                tileset_index,
+                tiles,
+                layer_transform,
+            )?;
+        } else {
+            layer_cmds.insert(layer_transform);
+
+            let mut result = Ok(());
+            layer_cmds.with_children(|builder| {
+                result = by_tileset.into_iter().try_for_each(|(tileset_index, tiles)| {
+                    let tileset = tiles[0].1.get_tileset();
+                    let mut tilemap_cmds = builder.spawn((
+                        TransformBundle::default(),
+                        VisibilityBundle::default(),
+                        Name::new(tileset.name.clone()),
+                    ));
+
+                    Self::spawn_tilemap(
+                        state,
+                        &mut tilemap_cmds,
+                        callback_selector,
+                        tilemap_texture_data,
+                        animations,
+                        tileset_index,
+                        tiles,
+                        Transform::default(),
+                    )
+                    .context(format!("While spawning the tiles of tileset {:?}", tileset.name))
+                });
+            });
+            result?;
+        }
+
+        state.next_layer()
+    }
+
+    /// Spawns the tiles of one tileset as a tilemap covering the
+    /// [TileBounds] of the map.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_tilemap(
+        state: &mut ParserState,
+        tilemap_cmds: &mut EntityCommands,
+        callback_selector: &mut C,
+        tilemap_texture_data: &[(TilesetIndexing, TilemapTexture)],
+        animations: &TilesetAnimations,
+        tileset_index: usize,
+        tiles: Vec<((i32, i32), LayerTile)>,
+        transform: Transform,
+    ) -> anyhow::Result<()> {
+        let tileset = tiles[0].1.get_tileset();
+        let provider = callback_selector.select(tileset)?;
+        let bounds = state.bounds;
+        let tilemap_size = bounds.tilemap_size();
         let mut storage = TileStorage::empty(tilemap_size);
-        let parent_id = layer_cmds.id();
+        let parent_id = tilemap_cmds.id();
 
         let mut result = Ok(());
         // Spawn the tiles
-        layer_cmds.with_children(|builder| {
-            let local_res = iproduct!(0..tiles.map().width, 0..tiles.map().height)
-            .filter_map(|(x, y)|
-                tiles.get_tile_data(x as i32, y as i32)
-                .map(|data| (x, y, data))
-            )
-            .try_for_each(|(x, y, tile)| {
+        tilemap_cmds.with_children(|builder| {
+            result = tiles.into_iter()
+            .try_for_each(|((x, y), tile)| {
+                let pos = bounds.tile_pos((x, y))
+                    .ok_or_else(|| anyhow!("The tile ({x}, {y}) is out of the map bounds"))?;
                 let (pos, e) = Self::spawn_tile(
                     state,
-                    (x, tiles.map().height - 1 - y),
+                    pos,
                     parent_id,
                     tileset_index,
                     tilemap_texture_data,
-                    tile,
+                    animations,
+                    &tile,
                     builder,
                     provider
                 )
@@ -301,45 +543,132 @@ where
 
                 Ok(())
             });
+        });
+        result?;
 
-            if local_res.is_err() {
-                result = local_res;
-            }
-        })
-        .insert(TilemapBundle {
+        tilemap_cmds.insert(TilemapBundle {
             storage,
             texture: tilemap_texture_data[tileset_index].1.clone(),
             map_type: TilemapType::Square,
             tile_size: TilemapTileSize { x: tileset.tile_width as f32, y: tileset.tile_height as f32 },
             grid_size: TilemapGridSize { x: tileset.tile_width as f32, y: tileset.tile_height as f32 },
             size: tilemap_size,
-            transform: Transform::from_translation(Vec3::new(
-                0.0f32,
-                0.0f32,
-                state.layer_idx as f32,
-            )),
+            transform,
             ..default()
         });
 
-        provider.finish_layer(tileset_index, layer_cmds)?;
-        ensure!(state.layer_idx < 100);
-        state.layer_idx += 1;
+        provider.finish_layer(tileset_index, tilemap_cmds)
+    }
 
-        result
+    fn parse_image_layer(
+        state: &mut ParserState,
+        layer_cmds: &mut EntityCommands,
+        layer_images: &LayerImages,
+        layer: Layer,
+        image_layer: ImageLayer,
+    ) -> anyhow::Result<()> {
+        use itertools::iproduct;
+
+        let image = match image_layer.image.as_ref() {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        let texture = layer_images.get(&image.source)
+            .ok_or_else(|| anyhow!("The image {:?} wasn't loaded with the map", image.source))?;
+
+        let map = layer.map();
+        let tile_size = Vec2::new(map.tile_width as f32, map.tile_height as f32);
+        let map_size = state.bounds.size.as_vec2() * tile_size;
+        let image_size = Vec2::new(image.width as f32, image.height as f32);
+        ensure!(image_size.x > 0.0 && image_size.y > 0.0, "The image {:?} is empty", image.source);
+
+        // `Tiled` places the images relative to the top left corner of the
+        // map, while the tilemaps are centered on their bottom left tile
+        let map_corner = Vec2::new(-tile_size.x / 2.0, map_size.y - tile_size.y / 2.0);
+        let offset = Vec2::new(layer.offset_x, layer.offset_y)
+            - state.bounds.min.as_vec2() * tile_size;
+        // The range of image copies needed to cover the map on an axis
+        let copies = |repeat: bool, offset: f32, image: f32, map: f32| match repeat {
+            true => (-(offset / image).ceil() as i32)..((map - offset) / image).ceil() as i32,
+            false => 0..1,
+        };
+        let sprite = Sprite {
+            color: Color::rgba(1.0, 1.0, 1.0, layer.opacity),
+            anchor: Anchor::TopLeft,
+            ..default()
+        };
+
+        layer_cmds.with_children(|builder| {
+            for (i, j) in iproduct!(
+                copies(image_layer.repeat_x, offset.x, image_size.x, map_size.x),
+                copies(image_layer.repeat_y, offset.y, image_size.y, map_size.y)
+            ) {
+                let pos = offset + Vec2::new(i as f32, j as f32) * image_size;
+
+                builder.spawn((
+                    SpriteBundle {
+                        sprite: sprite.clone(),
+                        texture: texture.clone(),
+                        transform: Transform::from_translation(Vec3::new(
+                            map_corner.x + pos.x,
+                            map_corner.y - pos.y,
+                            0.0f32,
+                        )),
+                        ..default()
+                    },
+                    Name::new("Image"),
+                ));
+            }
+        });
+
+        let origin = Vec3::new(0.0f32, 0.0f32, state.layer_idx as f32);
+        let factor = Vec2::new(layer.parallax_x, layer.parallax_y);
+        layer_cmds.insert(Transform::from_translation(origin));
+        if factor != Vec2::ONE {
+            layer_cmds.insert(TiledParallax { factor, origin });
+        }
+
+        state.next_layer()
+    }
+
+    fn parse_object_layer(
+        state: &ParserState,
+        layer_cmds: &mut EntityCommands,
+        object_builder: &mut dyn ObjectBuilder,
+        objects: ObjectLayer,
+    ) -> anyhow::Result<()> {
+        let map = objects.map();
+
+        let mut result = Ok(());
+        layer_cmds.with_children(|builder| {
+            result = objects.objects().try_for_each(|object| {
+                let object = MapObject::new(&object, map, &state.bounds);
+                let mut object_cmds = builder.spawn((
+                    TransformBundle::default(),
+                    Name::new(object.name().to_owned()),
+                ));
+
+                object_builder.build(&object, &mut object_cmds)
+                    .context(format!("Error while spawning object {:?}", object.name()))
+            });
+        });
+        result?;
+
+        object_builder.finish_layer(layer_cmds)
     }
 
     #[allow(clippy::too_many_arguments)]
     fn spawn_tile(
         _state: &mut ParserState,
-        (x, y): (u32, u32),
+        position: TilePos,
         parent_id: Entity,
         tileset_index: usize,
         tilemap_texture_data: &[(TilesetIndexing, TilemapTexture)],
+        animations: &TilesetAnimations,
         tile: &LayerTileData,
         builder: &mut ChildBuilder,
         tile_builder: &mut dyn TileBuilder,
     ) -> anyhow::Result<(TilePos, Entity)> {
-        let position = TilePos { x, y };
         let mut tile_commands = builder.spawn((
             TileBundle {
                 position,
@@ -354,28 +683,12 @@ where
             Name::new("Tile"),
         ));
 
+        if let Some(animation) = animations.get(tileset_index, tile.id()) {
+            tile_commands.insert(CPUAnimated::new(animation.clone(), true, false));
+        }
+
         tile_builder.build(tileset_index, tile.id(), &mut tile_commands)?;
 
         Ok((position, tile_commands.id()))
     }
 }
-
-fn ensure_unique_tileset<'a>(layer: &'a tiled::FiniteTileLayer) -> Result<(usize, &'a Tileset), anyhow::Error> {
-    let mut result = None;
-
-    for x in 0..layer.map().width {
-        for y in 0..layer.map().height {
-            if let Some(tile) = layer.get_tile(x as i32, y as i32) {
-                let tileset = (tile.tileset_index(), tile.get_tileset());
-
-                ensure!(
-                    result.is_none() || result == Some(tileset),
-                    "The tileset is using more than one tileset"
-                );
-                result = Some(tileset);
-            }
-        }
-    }
-
-    result.ok_or_else(|| anyhow!("The layer uses no tileset"))
-}
\ No newline at end of file
@@ -0,0 +1,122 @@
+//! Module which houses a tile builder driven by reflection. The class of
+//! a tile names a component registered in the [AppTypeRegistry] and the
+//! properties of the tile become its fields, so a component can be placed
+//! from the editor without writing a builder for it.
+
+use std::any::TypeId;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context};
+use bevy::ecs::system::{Command, EntityCommands};
+use bevy::prelude::*;
+use bevy::reflect::serde::TypedReflectDeserializer;
+use serde::de::DeserializeSeed;
+
+use crate::{TileBuilder, TilePropertyDes, TilesetData};
+
+/// Inserts the components of the tiles, which have been deserialized
+/// through reflection. The components need `#[reflect(Component)]` and
+/// have to be registered with `App::register_type`. Tiles without a class
+/// get no component.
+pub struct ReflectTileBuilder<F = fn(&mut EntityCommands)>
+where
+    F: Fn(&mut EntityCommands),
+{
+    registry: AppTypeRegistry,
+    bundle_builder: F,
+    components: HashMap<(usize, u32), (TypeId, Box<dyn Reflect>)>,
+}
+
+impl<F> ReflectTileBuilder<F>
+where
+    F: Fn(&mut EntityCommands),
+{
+    pub fn new(registry: AppTypeRegistry, bundle_builder: F) -> Self {
+        ReflectTileBuilder {
+            registry,
+            bundle_builder,
+            components: HashMap::new(),
+        }
+    }
+}
+
+impl<F> TileBuilder for ReflectTileBuilder<F>
+where
+    F: Fn(&mut EntityCommands),
+{
+    fn process_tileset(
+        &mut self,
+        set_id: usize,
+        tileset: &TilesetData,
+    ) -> anyhow::Result<()> {
+        let registry = self.registry.read();
+
+        for (id, tile) in tileset.tileset.tiles() {
+            let class = match tile.user_type.as_deref() {
+                Some(x) => x,
+                None => continue,
+            };
+            let registration = registry.get_with_short_name(class)
+                .ok_or_else(|| anyhow!("Tile {id} has class {class:?}, which isn't a registered type"))?;
+            if registration.data::<ReflectComponent>().is_none() {
+                anyhow::bail!("Tile {id} has class {class:?}, which doesn't reflect `Component`");
+            }
+
+            let component = TypedReflectDeserializer::new(registration, &registry)
+                .deserialize(TilePropertyDes::from_tile(&tile))
+                .with_context(|| format!("While deserializing tile {id}"))?;
+            self.components.insert((set_id, id), (registration.type_id(), component));
+        }
+
+        Ok(())
+    }
+
+    fn build(
+        &mut self,
+        set_id: usize,
+        id: u32,
+        cmds: &mut EntityCommands
+    ) -> anyhow::Result<()> {
+        if let Some((type_id, component)) = self.components.get(&(set_id, id)) {
+            let entity = cmds.id();
+
+            cmds.commands().add(InsertReflected {
+                registry: self.registry.clone(),
+                entity,
+                type_id: *type_id,
+                component: component.clone_value(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn finish_layer(
+        &mut self,
+        _set_id: usize,
+        cmds: &mut EntityCommands
+    ) -> anyhow::Result<()> {
+        (self.bundle_builder)(cmds);
+
+        Ok(())
+    }
+}
+
+/// Inserts a reflected component. Needs the world, because only
+/// [ReflectComponent] knows the type of the component.
+struct InsertReflected {
+    registry: AppTypeRegistry,
+    entity: Entity,
+    type_id: TypeId,
+    component: Box<dyn Reflect>,
+}
+
+impl Command for InsertReflected {
+    fn write(self, world: &mut World) {
+        let registry = self.registry.read();
+        // The builder has checked the component is reflected
+        let reflect_component = registry.get_type_data::<ReflectComponent>(self.type_id).unwrap();
+
+        reflect_component.insert(world, self.entity, &*self.component);
+    }
+}
@@ -1,19 +1,25 @@
-use serde::{ Deserializer, de::{ Error as DeError, Visitor, DeserializeSeed, MapAccess, IntoDeserializer, EnumAccess, VariantAccess } };
+use serde::{ Deserializer, de::{ Error as DeError, Visitor, DeserializeSeed, MapAccess, SeqAccess, IntoDeserializer, EnumAccess, VariantAccess } };
+use std::collections::hash_map;
 use std::fmt::Display;
+use std::path::Path;
 use thiserror::Error;
 
+use crate::tiled_map_asset::normalize_path;
+
 #[derive(Debug, Error)]
 pub enum PropertyDeserError {
     #[error("Deserializing property into {ty:} isn't supported")]
     UnsupportedType { ty: &'static str },
-    #[error("Can't deserialize object value into anything")]
-    ObjectValueFound,
     #[error("Mismatched property type. Expected {expected:} found {found:}")]
     WrongPropType { expected: &'static str, found: &'static str },
+    #[error("Expected a non-negative int, found {value:}")]
+    NegativeInt { value: i32 },
     #[error("Expected string to have exactly 1 char")]
     ExpectedChar,
     #[error("The field type was color, but the struct has fields other than r, g, b and a")]
     ColorFieldsExhausted,
+    #[error("Failed to parse member {name:?}. {source:}")]
+    MemberFail { name: String, source: Box<PropertyDeserError> },
     #[error("{custom:}")]
     Custom { custom: String },
 }
@@ -52,8 +58,146 @@ impl<'de> MapAccess<'de> for ColorMapper {
     }
 }
 
+/// Walks over a set of properties, deserializing each of them with
+/// [PropertyDes]. `wrap` puts the name of the property into the errors.
+struct PropertyMapper<'de, E> {
+    curr: Option<(&'de String, &'de tiled::PropertyValue)>,
+    it: hash_map::Iter<'de, String, tiled::PropertyValue>,
+    base_dir: Option<&'de Path>,
+    wrap: fn(String, PropertyDeserError) -> E,
+}
+
+impl<'de, E> PropertyMapper<'de, E> {
+    fn new(
+        properties: &'de tiled::Properties,
+        base_dir: Option<&'de Path>,
+        wrap: fn(String, PropertyDeserError) -> E,
+    ) -> Self {
+        PropertyMapper { curr: None, it: properties.iter(), base_dir, wrap }
+    }
+}
+
+impl<'de, E: DeError> MapAccess<'de> for PropertyMapper<'de, E> {
+    type Error = E;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        self.curr = self.it.next();
+
+        match self.curr {
+            Some((s, _)) => seed.deserialize(s.as_str().into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        let (name, prop) = self.curr.expect("Called after the fields were over or before `next_key_seed`");
+
+        seed.deserialize(PropertyDes { prop, base_dir: self.base_dir })
+            .map_err(|source| (self.wrap)(name.to_owned(), source))
+    }
+}
+
+/// `Tiled` has no arrays, so a sequence is written as properties named
+/// `0`, `1`, `2` and so on. The sequence ends at the first missing index.
+struct IndexedSeq<'de, E> {
+    properties: &'de tiled::Properties,
+    idx: usize,
+    base_dir: Option<&'de Path>,
+    wrap: fn(String, PropertyDeserError) -> E,
+}
+
+impl<'de, E> IndexedSeq<'de, E> {
+    fn new(
+        properties: &'de tiled::Properties,
+        base_dir: Option<&'de Path>,
+        wrap: fn(String, PropertyDeserError) -> E,
+    ) -> Self {
+        IndexedSeq { properties, idx: 0, base_dir, wrap }
+    }
+}
+
+impl<'de, E: DeError> SeqAccess<'de> for IndexedSeq<'de, E> {
+    type Error = E;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        let name = self.idx.to_string();
+        let prop = match self.properties.get(&name) {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        self.idx += 1;
+
+        seed.deserialize(PropertyDes { prop, base_dir: self.base_dir })
+            .map(Some)
+            .map_err(|source| (self.wrap)(name, source))
+    }
+}
+
+fn member_fail(name: String, source: PropertyDeserError) -> PropertyDeserError {
+    PropertyDeserError::MemberFail { name, source: Box::new(source) }
+}
+
+/// An enum variant with data, written as a class property. The class is
+/// named after the variant and its members are the data: `0`, `1` and so
+/// on for tuple variants and the field names for struct variants.
+struct ClassVariant<'de> {
+    variant: &'de str,
+    properties: &'de tiled::Properties,
+    base_dir: Option<&'de Path>,
+}
+
+impl<'de> EnumAccess<'de> for ClassVariant<'de> {
+    type Error = PropertyDeserError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let val = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((val, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ClassVariant<'de> {
+    type Error = PropertyDeserError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let prop = self.properties.get("0")
+            .ok_or_else(|| PropertyDeserError::missing_field("0"))?;
+
+        seed.deserialize(PropertyDes { prop, base_dir: self.base_dir })
+            .map_err(|source| member_fail("0".to_owned(), source))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>
+    {
+        visitor.visit_seq(IndexedSeq::new(self.properties, self.base_dir, member_fail))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>
+    {
+        visitor.visit_map(PropertyMapper::new(self.properties, self.base_dir, member_fail))
+    }
+}
+
+/// Deserializes a single property. Class properties become structs, maps
+/// or enum variants with data, missing properties become `None` and so do
+/// `0` object references and empty file paths. File paths deserialized
+/// into a `String` are read relative to `base_dir`, the directory of the
+/// file the property is in. A borrowed `&str` gets the path as it is
+/// written in the file, since the resolved one has nothing to borrow from.
 pub struct PropertyDes<'de> {
     prop: &'de tiled::PropertyValue,
+    base_dir: Option<&'de Path>,
 }
 
 impl<'de> PropertyDes<'de> {
@@ -68,6 +212,7 @@ impl<'de> PropertyDes<'de> {
             StringValue(_) => "string",
             FileValue(_) => "file",
             ObjectValue(_) => "object",
+            ClassValue { .. } => "class",
         }
     }
 
@@ -89,7 +234,7 @@ impl<'de> PropertyDes<'de> {
         match &self.prop {
             FloatValue(x) => Ok(*x),
             _ => Err(PropertyDeserError::WrongPropType {
-                expected: "bool",
+                expected: "float",
                 found: self.prop_type_str(),
             }),
         }
@@ -106,7 +251,44 @@ impl<'de> PropertyDes<'de> {
             }),
         }
     }
+
+    /// Object references are unsigned IDs, so they can be read as ints too.
+    /// Negative ints are rejected instead of wrapping around.
+    fn parse_u32(&self) -> Result<u32, PropertyDeserError> {
+        use tiled::PropertyValue::*;
+
+        match &self.prop {
+            ObjectValue(x) => Ok(*x),
+            _ => {
+                let value = self.parse_i32()?;
+                u32::try_from(value).map_err(|_| PropertyDeserError::NegativeInt { value })
+            },
+        }
+    }
+
+    fn parse_class(&self) -> Result<&'de tiled::Properties, PropertyDeserError> {
+        use tiled::PropertyValue::*;
+
+        match &self.prop {
+            ClassValue { properties, .. } => Ok(properties),
+            _ => Err(PropertyDeserError::WrongPropType {
+                expected: "class",
+                found: self.prop_type_str(),
+            }),
+        }
+    }
     
+    /// The asset path of a file property. `None` if the property isn't a
+    /// file or there's no directory to resolve it against.
+    fn resolve_file(&self) -> Option<String> {
+        match (self.prop, self.base_dir) {
+            (tiled::PropertyValue::FileValue(x), Some(dir)) => Some(
+                normalize_path(&dir.join(x)).to_string_lossy().into_owned()
+            ),
+            _ => None,
+        }
+    }
+
     fn parse_str(&self) -> Result<&'de str, PropertyDeserError> {
         use tiled::PropertyValue::*;
 
@@ -132,7 +314,8 @@ impl<'de> Deserializer<'de> for PropertyDes<'de> {
             IntValue(_) => self.deserialize_i32(visitor),
             ColorValue(_) => self.deserialize_struct("JUNK", &[], visitor),
             StringValue(_) | FileValue(_) => self.deserialize_string(visitor),
-            ObjectValue(_) => Err(PropertyDeserError::ObjectValueFound),
+            ObjectValue(_) => self.deserialize_u32(visitor),
+            ClassValue { .. } => self.deserialize_map(visitor),
         }
     }
 
@@ -157,19 +340,19 @@ impl<'de> Deserializer<'de> for PropertyDes<'de> {
     }
 
     fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
-        visitor.visit_u32(self.parse_i32()? as u32)
+        visitor.visit_u32(self.parse_u32()?)
     }
 
     fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
-        visitor.visit_u32(self.parse_i32()? as u32)
+        visitor.visit_u32(self.parse_u32()?)
     }
 
     fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
-        visitor.visit_u32(self.parse_i32()? as u32)
+        visitor.visit_u32(self.parse_u32()?)
     }
 
     fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
-        visitor.visit_u32(self.parse_i32()? as u32)
+        visitor.visit_u64(self.parse_u32()?.into())
     }
 
     fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
@@ -198,7 +381,10 @@ impl<'de> Deserializer<'de> for PropertyDes<'de> {
     }
 
     fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
-        visitor.visit_borrowed_str(self.parse_str()?)
+        match self.resolve_file() {
+            Some(path) => visitor.visit_string(path),
+            None => self.deserialize_str(visitor),
+        }
     }
 
     fn deserialize_bytes<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
@@ -209,8 +395,13 @@ impl<'de> Deserializer<'de> for PropertyDes<'de> {
         Err(PropertyDeserError::UnsupportedType { ty: "byte buffer" })
     }
 
-    fn deserialize_option<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(PropertyDeserError::UnsupportedType { ty: "option" })
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // A missing property never gets here, serde turns it into `None`
+        match self.prop {
+            tiled::PropertyValue::ObjectValue(0) => visitor.visit_none(),
+            tiled::PropertyValue::FileValue(x) if x.is_empty() => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
     }
 
     fn deserialize_unit<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
@@ -221,26 +412,29 @@ impl<'de> Deserializer<'de> for PropertyDes<'de> {
         Err(PropertyDeserError::UnsupportedType { ty: "unit struct" })
     }
 
-    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(PropertyDeserError::UnsupportedType { ty: "newtype struct" })
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
     }
 
-    fn deserialize_seq<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(PropertyDeserError::UnsupportedType { ty: "sequence" })
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(IndexedSeq::new(self.parse_class()?, self.base_dir, member_fail))
     }
 
-    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(PropertyDeserError::UnsupportedType { ty: "tuple" })
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
     }
 
-    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(PropertyDeserError::UnsupportedType { ty: "tuple struct" })
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
     }
 
     fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
         match &self.prop {
             tiled::PropertyValue::ColorValue(col) => visitor.visit_map(ColorMapper(*col, 0)),
-            _ => Err(PropertyDeserError::WrongPropType { expected: "color", found: self.prop_type_str() }),
+            tiled::PropertyValue::ClassValue { properties, .. } => visitor.visit_map(
+                PropertyMapper::new(properties, self.base_dir, member_fail)
+            ),
+            _ => Err(PropertyDeserError::WrongPropType { expected: "color or class", found: self.prop_type_str() }),
         }
     }
     
@@ -259,7 +453,15 @@ impl<'de> Deserializer<'de> for PropertyDes<'de> {
         _variants: &'static [&'static str],
         visitor: V
     ) -> Result<V::Value, Self::Error> {
-        visitor.visit_enum(self.parse_str()?.into_deserializer())
+        // Unit variants are plain strings, the rest are classes
+        match &self.prop {
+            tiled::PropertyValue::ClassValue { property_type, properties } => visitor.visit_enum(ClassVariant {
+                variant: property_type,
+                properties,
+                base_dir: self.base_dir,
+            }),
+            _ => visitor.visit_enum(self.parse_str()?.into_deserializer()),
+        }
     }
     
     fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
@@ -273,7 +475,7 @@ impl<'de> Deserializer<'de> for PropertyDes<'de> {
 
 #[derive(Debug, Error)]
 pub enum TilePropertyDeserError {
-    #[error("Only structs can be deserialized from tiled properties")]
+    #[error("Only structs, enums and sequences can be deserialized from tiled properties")]
     OnlyStruct,
     #[error("The tile didn't have a typed specified")]
     NoType,
@@ -281,8 +483,6 @@ pub enum TilePropertyDeserError {
     WrongType { expected: &'static str, found: String },
     #[error("Failed to parse property {name:?}. {source:}")]
     PropFail { name: String, source: PropertyDeserError },
-    #[error("Tuple enum variants aren't supported")]
-    TupleVariantNotSupported,
     #[error("{custom:}")]
     Custom { custom: String },
 }
@@ -293,33 +493,12 @@ impl DeError for TilePropertyDeserError {
     }
 }
 
-struct TilePropertyMapper<'de> {
-    curr: Option<(&'de String, &'de tiled::PropertyValue)>,
-    it: std::collections::hash_map::Iter<'de, String, tiled::PropertyValue>,
-}
-
-impl<'de> MapAccess<'de> for TilePropertyMapper<'de> {
-    type Error = TilePropertyDeserError;
-
-    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
-        self.curr = self.it.next();
-
-        match self.curr {
-            Some((s, _)) => seed.deserialize(s.as_str().into_deserializer()).map(Some),
-            None => Ok(None),
-        }
-    }
-
-    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
-        let (name, prop) = self.curr.expect("Called after the fields were over or before `next_key_seed`");
-
-        seed.deserialize(PropertyDes { prop })
-            .map_err(|source| TilePropertyDeserError::PropFail { name: name.to_owned(), source })
-    }
+fn prop_fail(name: String, source: PropertyDeserError) -> TilePropertyDeserError {
+    TilePropertyDeserError::PropFail { name, source }
 }
 
 struct TilePropertyEnum<'de> {
-    tile: &'de tiled::Tile<'de>,
+    des: TilePropertyDes<'de>,
 }
 
 impl<'de> EnumAccess<'de> for TilePropertyEnum<'de> {
@@ -327,9 +506,8 @@ impl<'de> EnumAccess<'de> for TilePropertyEnum<'de> {
     type Variant = Self;
 
     fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> {
-        let val = seed.deserialize(self.tile.tile_type.as_ref()
+        let val = seed.deserialize(self.des.class
             .ok_or(TilePropertyDeserError::NoType)?
-            .as_str()
             .into_deserializer()
         )?;
         Ok((val, self))
@@ -347,26 +525,56 @@ impl<'de> VariantAccess<'de> for TilePropertyEnum<'de> {
     where
         T: DeserializeSeed<'de>,
     {
-        seed.deserialize(TilePropertyDes { tile: self.tile })
+        seed.deserialize(self.des)
     }
 
-    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error> 
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> 
     where
         V: Visitor<'de>
     {
-        Err(TilePropertyDeserError::TupleVariantNotSupported)
+        self.des.deserialize_seq(visitor)
     }
 
     fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>
     {
-        TilePropertyDes { tile: self.tile }.deserialize_map(visitor)
+        self.des.deserialize_map(visitor)
     }
 }
 
-pub struct TilePropertyDes<'de> { 
-    pub tile: &'de tiled::Tile<'de>,
+/// Deserializes the custom properties of a tile or an object. The class
+/// of the tile decides the enum variant and must match the struct name.
+#[derive(Clone, Copy)]
+pub struct TilePropertyDes<'de> {
+    class: Option<&'de str>,
+    properties: &'de tiled::Properties,
+    base_dir: Option<&'de Path>,
+}
+
+impl<'de> TilePropertyDes<'de> {
+    pub fn new(class: Option<&'de str>, properties: &'de tiled::Properties) -> Self {
+        TilePropertyDes { class, properties, base_dir: None }
+    }
+
+    /// Makes the file properties get read as asset paths, relative to
+    /// `dir`. Without it they are left as they are in the file.
+    pub fn with_base_dir(self, dir: Option<&'de Path>) -> Self {
+        TilePropertyDes { base_dir: dir, ..self }
+    }
+
+    /// The file properties of the tile are relative to its tileset.
+    pub fn from_tile(tile: &'de tiled::Tile<'de>) -> Self {
+        Self::new(tile.user_type.as_deref(), &tile.properties)
+            .with_base_dir(tile.tileset().source.parent())
+    }
+
+    /// Objects without a class have an empty one.
+    pub fn from_object(object: &'de tiled::ObjectData) -> Self {
+        let class = Some(object.user_type.as_str()).filter(|x| !x.is_empty());
+
+        Self::new(class, &object.properties)
+    }
 }
 
 impl<'de> Deserializer<'de> for TilePropertyDes<'de> {
@@ -453,27 +661,24 @@ impl<'de> Deserializer<'de> for TilePropertyDes<'de> {
         Err(TilePropertyDeserError::OnlyStruct)
     }
 
-    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(TilePropertyDeserError::OnlyStruct)
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
     }
 
-    fn deserialize_seq<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(TilePropertyDeserError::OnlyStruct)
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(IndexedSeq::new(self.properties, self.base_dir, prop_fail))
     }
 
-    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(TilePropertyDeserError::OnlyStruct)
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
     }
 
-    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, _visitor: V) -> Result<V::Value, Self::Error> {
-        Err(TilePropertyDeserError::OnlyStruct)
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
     }
 
     fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
-        visitor.visit_map(TilePropertyMapper {
-            curr: None,
-            it: self.tile.properties.iter(),
-        })
+        visitor.visit_map(PropertyMapper::new(self.properties, self.base_dir, prop_fail))
     }
     
     fn deserialize_struct<V: Visitor<'de>>(
@@ -482,9 +687,7 @@ impl<'de> Deserializer<'de> for TilePropertyDes<'de> {
         _fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error> {
-        let ty = self.tile.tile_type.as_ref()
-            .ok_or(TilePropertyDeserError::NoType)?
-            .as_str();
+        let ty = self.class.ok_or(TilePropertyDeserError::NoType)?;
 
         if ty != name { 
             return Err(TilePropertyDeserError::WrongType { 
@@ -502,7 +705,7 @@ impl<'de> Deserializer<'de> for TilePropertyDes<'de> {
         _variants: &'static [&'static str],
         visitor: V
     ) -> Result<V::Value, Self::Error> {
-        visitor.visit_enum(TilePropertyEnum { tile: self.tile })
+        visitor.visit_enum(TilePropertyEnum { des: self })
     }
     
     fn deserialize_identifier<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
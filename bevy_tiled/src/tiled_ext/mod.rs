@@ -7,8 +7,11 @@ mod deser_impl;
 
 use bevy_ecs_tilemap::prelude::*;
 use serde::{ Deserialize, de::DeserializeOwned, Deserializer };
-use std::collections::HashMap;
-use tiled::{ Layer, Map, LayerType, TileLayer, FiniteTileLayer, GroupLayer };
+use std::collections::{ BTreeMap, HashMap };
+use std::path::PathBuf;
+use bevy::asset::{ Asset, AssetPath, Handle };
+use bevy::math::{ IVec2, UVec2 };
+use tiled::{ Layer, LayerTile, Map, LayerType, TileLayer, FiniteTileLayer, GroupLayer, ChunkData };
 
 pub use deser_impl::*;
 
@@ -59,13 +62,127 @@ impl TiledLayerTileExt for tiled::LayerTileData {
     }
 }
 
+pub trait TileLayerExt<'map> {
+    /// All the tiles of the layer together with their positions in
+    /// `Tiled`'s coordinates. The positions of an infinite layer's tiles
+    /// can be negative.
+    fn tiles(&self) -> Vec<((i32, i32), LayerTile<'map>)>;
+
+    /// Same as [TileLayerExt::tiles], but grouped by the tileset index.
+    fn tiles_by_tileset(&self) -> BTreeMap<usize, Vec<((i32, i32), LayerTile<'map>)>> {
+        let mut result = BTreeMap::<_, Vec<_>>::new();
+
+        for (pos, tile) in self.tiles() {
+            result.entry(tile.tileset_index()).or_default().push((pos, tile));
+        }
+
+        result
+    }
+}
+
+impl<'map> TileLayerExt<'map> for TileLayer<'map> {
+    fn tiles(&self) -> Vec<((i32, i32), LayerTile<'map>)> {
+        let positions: Vec<(i32, i32)> = match self {
+            TileLayer::Finite(layer) => itertools::iproduct!(
+                0..layer.map().width as i32,
+                0..layer.map().height as i32
+            ).collect(),
+            TileLayer::Infinite(layer) => layer.chunks()
+                .flat_map(|((cx, cy), _)| itertools::iproduct!(
+                    cx * ChunkData::WIDTH as i32..(cx + 1) * ChunkData::WIDTH as i32,
+                    cy * ChunkData::HEIGHT as i32..(cy + 1) * ChunkData::HEIGHT as i32
+                ))
+                .collect(),
+        };
+
+        positions.into_iter()
+            .filter_map(|(x, y)| self.get_tile(x, y).map(|tile| ((x, y), tile)))
+            .collect()
+    }
+}
+
+/// The part of the map covered by the tiles, in `Tiled`'s coordinates.
+/// For a finite map it's the whole map. For an infinite one it's the
+/// smallest rectangle holding the tiles of all the tile layers. The
+/// objects and the image layers don't count, so an object away from the
+/// tiles ends up outside of the bounds. The corner of the bounds becomes
+/// the origin of the tilemaps, so that the tile positions are never
+/// negative.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TileBounds {
+    pub min: IVec2,
+    pub size: UVec2,
+}
+
+impl TileBounds {
+    pub fn of_map(map: &Map) -> Self {
+        if !map.infinite() {
+            return TileBounds {
+                min: IVec2::ZERO,
+                size: UVec2::new(map.width, map.height),
+            };
+        }
+
+        let mut positions = Vec::new();
+        collect_tile_positions(map.layers(), &mut positions);
+
+        let min = positions.iter().copied().reduce(IVec2::min);
+        let max = positions.iter().copied().reduce(IVec2::max);
+        match min.zip(max) {
+            Some((min, max)) => TileBounds {
+                min,
+                size: (max - min + IVec2::ONE).as_uvec2(),
+            },
+            None => TileBounds::default(),
+        }
+    }
+
+    /// Converts a position in `Tiled`'s coordinates into a tile position
+    /// with `y` growing upwards. `None` if it's out of bounds.
+    pub fn tile_pos(&self, (x, y): (i32, i32)) -> Option<TilePos> {
+        let pos = IVec2::new(x, y) - self.min;
+
+        (pos.cmpge(IVec2::ZERO).all() && pos.as_uvec2().cmplt(self.size).all())
+            .then(|| TilePos { x: pos.x as u32, y: self.size.y - 1 - pos.y as u32 })
+    }
+
+    pub fn tilemap_size(&self) -> TilemapSize {
+        TilemapSize { x: self.size.x, y: self.size.y }
+    }
+}
+
+fn collect_tile_positions<'map>(
+    layers: impl Iterator<Item = Layer<'map>>,
+    out: &mut Vec<IVec2>,
+) {
+    for layer in layers {
+        match layer.layer_type() {
+            LayerType::TileLayer(tiles) => out.extend(
+                tiles.tiles().into_iter().map(|((x, y), _)| IVec2::new(x, y))
+            ),
+            LayerType::GroupLayer(group) => collect_tile_positions(group.layers(), out),
+            _ => (),
+        }
+    }
+}
+
 pub trait TileExt<'de> {
     fn properties<D: Deserialize<'de>>(&'de self) -> Result<D, TilePropertyDeserError>;
 }
 
 impl<'de> TileExt<'de> for tiled::Tile<'de> {
     fn properties<D: Deserialize<'de>>(&'de self) -> Result<D, TilePropertyDeserError> {
-        D::deserialize(TilePropertyDes { tile: self })
+        D::deserialize(TilePropertyDes::from_tile(self))
+    }
+}
+
+pub trait ObjectExt<'de> {
+    fn properties<D: Deserialize<'de>>(&'de self) -> Result<D, TilePropertyDeserError>;
+}
+
+impl<'de> ObjectExt<'de> for tiled::ObjectData {
+    fn properties<D: Deserialize<'de>>(&'de self) -> Result<D, TilePropertyDeserError> {
+        D::deserialize(TilePropertyDes::from_object(self))
     }
 }
 
@@ -86,12 +203,15 @@ impl<'a> LayerSearch for GroupLayer<'a> {
     fn layers(&self) -> Box<dyn ExactSizeIterator<Item = Layer<'_>> + '_> { Box::new(self.layers()) }
 }
 
-pub fn deserailize_from_json_str<'de, D, T>(des: D) -> Result<T, D::Error>
+/// Deserializes a file property into a handle of the asset it points to.
+/// The asset gets loaded as a dependency of the map or the tileset with
+/// the property, so the handle is weak.
+pub fn deserialize_asset_handle<'de, D, T>(des: D) -> Result<Handle<T>, D::Error>
 where
-    T: Deserialize<'de>,
+    T: Asset,
     D: Deserializer<'de>,
 {
-    let s = <&'_ str as Deserialize>::deserialize(des)?;
-    serde_json::from_str(s)
-        .map_err(<D::Error as serde::de::Error>::custom)
-}
\ No newline at end of file
+    let path = String::deserialize(des)?;
+
+    Ok(Handle::weak(AssetPath::new(PathBuf::from(path), None).into()))
+}
@@ -2,16 +2,21 @@
 //! together with its own asset loader.
 
 use std::collections::HashMap;
-use std::io::BufReader;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use anyhow::Context;
 use bevy::asset::{ AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset };
 use bevy_ecs_tilemap::prelude::TilemapTexture;
 use bevy_ecs_tilemap_cpu_anim::{ Frame, CPUTileAnimation };
 use bevy::prelude::*;
 use bevy::reflect::TypeUuid;
 
+use crate::tiled_tileset_asset::ParseCache;
+use crate::{TiledTilesetAsset, TilesetCache, TilesetData};
+
 pub fn tileset_indexing(
     In(map): In<Handle<TiledMap>>,
     images: Res<Assets<Image>>,
@@ -19,38 +24,44 @@ pub fn tileset_indexing(
 ) -> Vec<(TilesetIndexing, TilemapTexture)> {
     let map = maps.get(&map).unwrap();
     map.tilesets.iter()
-        .map(|(_, tileset)| match tileset {
-            TiledTileset::Image(path) => (
-                TilesetIndexing::Continious,
-                TilemapTexture::Single(images.get_handle(path.to_owned()))
-            ),
-            TiledTileset::ImageCollection(tiles) => (
-                TilesetIndexing::Special(
-                    tiles.iter()
-                    .map(|(id, _)| *id)
-                    .enumerate()
-                    .map(|(to, from)| (from, to as u32))
-                    .collect()
-                ),
-                TilemapTexture::Vector(
+        .map(|data| (
+            data.indexing.clone(),
+            match &data.source {
+                TiledTileset::Image(path) => TilemapTexture::Single(images.get_handle(path.to_owned())),
+                TiledTileset::ImageCollection(tiles) => TilemapTexture::Vector(
                     tiles.iter()
                     .map(|(_, path)| images.get_handle(path.to_owned()))
                     .collect()
-                )
-            ),
-        })
+                ),
+            },
+        ))
         .collect()
 }
 
 /// A type, which encodes mapping from `Tiled` tile IDs to
 /// engine's IDs in the tile atlas.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum TilesetIndexing {
     Continious,
     Special(HashMap<u32, u32>),
 }
 
 impl TilesetIndexing {
+    /// Constructs the mapping for a tileset, which has its images put into
+    /// a texture in the order of the tile IDs.
+    pub fn from_source(tileset_info: &TiledTileset) -> Self {
+        match tileset_info {
+            TiledTileset::Image(_) => Self::Continious,
+            TiledTileset::ImageCollection(tiles) => Self::Special(
+                tiles.iter()
+                .map(|(id, _)| *id)
+                .enumerate()
+                .map(|(to, from)| (from, to as u32))
+                .collect()
+            ),
+        }
+    }
+
     /// Constructs the mapping, given the compiled atlas
     /// and the tileset source info.
     pub fn from_tileset_and_atlas(
@@ -89,6 +100,56 @@ impl TilesetIndexing {
     }
 }
 
+/// The native `Tiled` animations of the map's tiles, turned into assets.
+/// The animations are keyed by the tileset index and the tile ID.
+#[derive(Default, Debug)]
+pub struct TilesetAnimations(HashMap<(usize, u32), Handle<CPUTileAnimation>>);
+
+impl TilesetAnimations {
+    pub fn from_map(
+        map: &tiled::Map,
+        tilemap_texture_data: &[(TilesetIndexing, TilemapTexture)],
+        assets: &mut Assets<CPUTileAnimation>,
+    ) -> Self {
+        let mut animations = HashMap::new();
+
+        for (set_id, tileset) in map.tilesets().iter().enumerate() {
+            let indexing = &tilemap_texture_data[set_id].0;
+
+            for (id, tile) in tileset.tiles() {
+                if let Some(anim) = tile.animation.as_ref() {
+                    animations.insert((set_id, id), assets.add(indexing.cpu_tile_anim(anim)));
+                }
+            }
+        }
+
+        TilesetAnimations(animations)
+    }
+
+    pub fn get(&self, set_id: usize, id: u32) -> Option<&Handle<CPUTileAnimation>> {
+        self.0.get(&(set_id, id))
+    }
+}
+
+/// The images of the map's image layers, keyed by the source path from
+/// the map file.
+#[derive(Default, Debug)]
+pub struct LayerImages(HashMap<PathBuf, Handle<Image>>);
+
+impl LayerImages {
+    pub fn from_map(map: &TiledMap, images: &Assets<Image>) -> Self {
+        LayerImages(
+            map.layer_images.iter()
+            .map(|(source, path)| (source.to_owned(), images.get_handle(path.to_owned())))
+            .collect()
+        )
+    }
+
+    pub fn get(&self, source: &Path) -> Option<&Handle<Image>> {
+        self.0.get(source)
+    }
+}
+
 /// Encodes the types for the tilset
 #[derive(Clone, Debug)]
 pub enum TiledTileset {
@@ -103,11 +164,157 @@ pub enum TiledTileset {
 #[uuid = "e51081d0-6168-4881-a1c6-4249b2000d7f"]
 pub struct TiledMap {
     pub map: tiled::Map,
-    pub tilesets: Vec<(Vec2, TiledTileset)>,
+    /// The data for each of the map's tilesets. The external ones are
+    /// shared with the other maps.
+    pub tilesets: Vec<Arc<TilesetData>>,
+    /// Keeps the external tilesets loaded between the maps
+    pub tileset_handles: Vec<Handle<TiledTilesetAsset>>,
+    /// The asset paths of the images used by the image layers
+    pub layer_images: HashMap<PathBuf, AssetPath<'static>>,
 }
 
-#[derive(Clone, Copy, Default)]
-pub struct TiledMapLoader;
+/// Collects the image sources of the image layers, including the nested ones.
+fn collect_layer_images<'map>(
+    layers: impl Iterator<Item = tiled::Layer<'map>>,
+    out: &mut Vec<PathBuf>,
+) {
+    for layer in layers {
+        match layer.layer_type() {
+            tiled::LayerType::ImageLayer(image_layer) => out.extend(
+                image_layer.image.as_ref().map(|image| image.source.clone())
+            ),
+            tiled::LayerType::GroupLayer(group) => collect_layer_images(group.layers(), out),
+            _ => (),
+        }
+    }
+}
+
+/// Collects the asset paths of the file properties, including the ones
+/// nested into class properties. The paths in the file are relative to
+/// `base_dir`.
+pub(crate) fn collect_files(
+    properties: &tiled::Properties,
+    base_dir: &Path,
+    out: &mut Vec<AssetPath<'static>>,
+) {
+    for prop in properties.values() {
+        match prop {
+            tiled::PropertyValue::FileValue(x) if !x.is_empty() => out.push(
+                AssetPath::new(normalize_path(&base_dir.join(x)), None)
+            ),
+            tiled::PropertyValue::ClassValue { properties, .. } => collect_files(properties, base_dir, out),
+            _ => (),
+        }
+    }
+}
+
+/// Collects the file properties of the layers and of their objects.
+fn collect_layer_files<'map>(
+    layers: impl Iterator<Item = tiled::Layer<'map>>,
+    base_dir: &Path,
+    out: &mut Vec<AssetPath<'static>>,
+) {
+    for layer in layers {
+        collect_files(&layer.properties, base_dir, out);
+
+        match layer.layer_type() {
+            tiled::LayerType::ObjectLayer(objects) => for object in objects.objects() {
+                collect_files(&object.properties, base_dir, out);
+            },
+            tiled::LayerType::GroupLayer(group) => collect_layer_files(group.layers(), base_dir, out),
+            _ => (),
+        }
+    }
+}
+
+/// Resolves the `.` and `..` in a path. `tiled` joins the paths of the
+/// dependencies to the map's directory as is, while the asset paths have
+/// to be the same no matter which map they come from.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => { result.pop(); },
+            x => result.push(x),
+        }
+    }
+
+    result
+}
+
+/// Serves the files `tiled` asks for from the ones already fetched
+/// through the asset IO. The first file it doesn't have gets remembered,
+/// so that the loader can fetch it and try again.
+pub(crate) struct FetchedFiles<'a> {
+    files: &'a HashMap<PathBuf, Vec<u8>>,
+    missing: &'a Mutex<Option<PathBuf>>,
+}
+
+impl<'a> tiled::ResourceReader for FetchedFiles<'a> {
+    type Resource = &'a [u8];
+    type Error = io::Error;
+
+    fn read_from(&mut self, path: &Path) -> Result<Self::Resource, Self::Error> {
+        let path = normalize_path(path);
+
+        match self.files.get(&path) {
+            Some(bytes) => Ok(bytes.as_slice()),
+            None => {
+                let err = io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{path:?} hasn't been fetched yet"),
+                );
+                *self.missing.lock().unwrap() = Some(path);
+
+                Err(err)
+            },
+        }
+    }
+}
+
+/// Runs `parse` until it succeeds, fetching the files it's missing
+/// through the asset IO. `tiled` reads the files synchronously, so all the
+/// external tilesets and templates are fetched one by one, restarting the
+/// parsing each time a new one is found.
+pub(crate) async fn parse_with_asset_io<T>(
+    load_context: &mut LoadContext<'_>,
+    path: &Path,
+    bytes: &[u8],
+    mut parse: impl FnMut(FetchedFiles) -> Result<T, tiled::Error>,
+) -> anyhow::Result<T> {
+    let mut files = HashMap::new();
+    files.insert(normalize_path(path), bytes.to_owned());
+
+    loop {
+        let missing = Mutex::new(None);
+        let result = parse(FetchedFiles {
+            files: &files,
+            missing: &missing,
+        });
+
+        let path = match (result, missing.into_inner().unwrap()) {
+            (Ok(x), _) => return Ok(x),
+            (Err(_), Some(path)) => path,
+            (Err(e), None) => return Err(e.into()),
+        };
+        let bytes = load_context.read_asset_bytes(&path).await
+            .with_context(|| format!("Loading {path:?}"))?;
+        files.insert(path, bytes);
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TiledMapLoader {
+    cache: TilesetCache,
+}
+
+impl TiledMapLoader {
+    pub fn new(cache: TilesetCache) -> Self {
+        TiledMapLoader { cache }
+    }
+}
 
 impl AssetLoader for TiledMapLoader {
     fn load<'a>(
@@ -115,54 +322,53 @@ impl AssetLoader for TiledMapLoader {
         bytes: &'a [u8],
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
-        // NOTE this is a workround, because of `tiled`'s bad compatability
-        // with `bevy`. It uses `fs::File` to load tileset, which ends up
-        // peeking into the crate root, rather into asset folder.
-        fn asset_dir_root() -> PathBuf {
-            #[cfg(target_arch = "x86_64")]
-            return bevy::asset::FileAssetIo::get_base_path();
-
-            #[cfg(target_arch = "wasm32")]
-            return PathBuf::new();
-        }
-
         Box::pin(async move {
-            let mut loader = tiled::Loader::new();
+            let map_path = load_context.path().to_owned();
+            let map = parse_with_asset_io(load_context, &map_path, bytes, |reader| {
+                let cache = ParseCache {
+                    tilesets: &self.cache,
+                    templates: HashMap::new(),
+                };
 
-            let root = asset_dir_root().join("assets");
-            // FIXME `tiled` loads dependencies using Rust's traditional file IO. That's very bad
-            // news for the WASM build.
-            let map = loader.load_tmx_map_from(BufReader::new(bytes), &root.join(load_context.path()))?;
-            let fix_asset_path = |x: &PathBuf| AssetPath::new(x.strip_prefix(&root).unwrap().to_path_buf(), None);
+                tiled::Loader::with_cache_and_reader(cache, reader).load_tmx_map(&map_path)
+            }).await?;
+            let fix_asset_path = |x: &PathBuf| AssetPath::new(normalize_path(x), None);
             let mut tilesets = Vec::new();
+            let mut tileset_handles = Vec::new();
             let mut dependencies = Vec::new();
 
             for tileset in map.tilesets() {
-                let tile_size = Vec2::new(tileset.tile_width as f32, tileset.tile_height as f32);
-                let tileset = match tileset.image.as_ref() {
-                    Some(image) => {
-                        let asset_path = fix_asset_path(&image.source);
-                        dependencies.push(asset_path.clone());
+                let data = match self.cache.find(tileset) {
+                    Some(data) => {
+                        let asset_path = fix_asset_path(&tileset.source);
+                        tileset_handles.push(load_context.get_handle(asset_path.clone()));
+                        dependencies.push(asset_path);
 
-                        TiledTileset::Image(asset_path)
-                    },
-                    None => {
-                        let asset_paths: Vec<(u32, AssetPath<'static>)> = tileset.tiles()
-                            .filter_map(|(tile_id, tile)|
-                                tile.image.as_ref().map(|x| (tile_id, fix_asset_path(&x.source)))
-                            )
-                            .collect();
-                        asset_paths.iter().for_each(|(_, path)| dependencies.push(path.to_owned()));
-
-                        TiledTileset::ImageCollection(asset_paths)
+                        data
                     },
+                    None => Arc::new(TilesetData::new(tileset.clone())),
                 };
 
-                tilesets.push((tile_size, tileset));
+                dependencies.extend(data.dependencies());
+                tilesets.push(data);
             }
+            let mut sources = Vec::new();
+            collect_layer_images(map.layers(), &mut sources);
+            let layer_images: HashMap<_, _> = sources.into_iter()
+                .map(|source| {
+                    let asset_path = fix_asset_path(&source);
+                    (source, asset_path)
+                })
+                .collect();
+            dependencies.extend(layer_images.values().cloned());
+
+            // The assets the file properties point to
+            let base_dir = map_path.parent().unwrap_or_else(|| Path::new(""));
+            collect_files(&map.properties, base_dir, &mut dependencies);
+            collect_layer_files(map.layers(), base_dir, &mut dependencies);
 
             let loaded_asset = LoadedAsset::new(TiledMap {
-                map, tilesets,
+                map, tilesets, tileset_handles, layer_images,
             }).with_dependencies(dependencies);
 
             load_context.set_default_asset(loaded_asset);
@@ -0,0 +1,230 @@
+//! Module which houses `tiled` tilesets wrapped into an asset. The tilesets
+//! are shared by all the maps using them, so a `.tsx` file gets parsed and
+//! its tile properties get deserialized only once, until the file changes.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use bevy::asset::{ AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset };
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::de::DeserializeOwned;
+use tiled::{ResourceCache, ResourcePath, Template, TileId, Tileset};
+
+use crate::tiled_map_asset::{collect_files, normalize_path, parse_with_asset_io};
+use crate::{TiledTileset, TilePropertyDeserError, TilesetExt, TilesetIndexing};
+
+/// A tileset together with everything the engine needs to know about it.
+pub struct TilesetData {
+    pub tileset: Arc<Tileset>,
+    pub source: TiledTileset,
+    pub indexing: TilesetIndexing,
+    /// The assets the file properties of the tileset and its tiles point to
+    pub files: Vec<AssetPath<'static>>,
+    properties: Mutex<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl TilesetData {
+    pub fn new(tileset: Arc<Tileset>) -> Self {
+        let fix_asset_path = |x: &Path| AssetPath::new(normalize_path(x), None);
+        let source = match tileset.image.as_ref() {
+            Some(image) => TiledTileset::Image(fix_asset_path(&image.source)),
+            None => TiledTileset::ImageCollection(
+                tileset.tiles()
+                .filter_map(|(tile_id, tile)|
+                    tile.image.as_ref().map(|x| (tile_id, fix_asset_path(&x.source)))
+                )
+                .collect()
+            ),
+        };
+
+        let base_dir = tileset.source.parent().unwrap_or_else(|| Path::new(""));
+        let mut files = Vec::new();
+        collect_files(&tileset.properties, base_dir, &mut files);
+        for (_, tile) in tileset.tiles() {
+            collect_files(&tile.properties, base_dir, &mut files);
+        }
+
+        TilesetData {
+            indexing: TilesetIndexing::from_source(&source),
+            files,
+            tileset,
+            source,
+            properties: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn tile_size(&self) -> Vec2 {
+        Vec2::new(self.tileset.tile_width as f32, self.tileset.tile_height as f32)
+    }
+
+    /// The images the tileset uses and the files its properties point to.
+    pub fn dependencies(&self) -> Vec<AssetPath<'static>> {
+        let images = match &self.source {
+            TiledTileset::Image(path) => vec![path.clone()],
+            TiledTileset::ImageCollection(paths) => paths.iter()
+                .map(|(_, path)| path.clone())
+                .collect(),
+        };
+
+        images.into_iter().chain(self.files.iter().cloned()).collect()
+    }
+
+    /// Deserializes the properties of all the tiles. The result is kept,
+    /// so each type gets deserialized once per tileset.
+    pub fn properties<D>(&self) -> Result<Arc<HashMap<TileId, D>>, TilePropertyDeserError>
+    where
+        D: DeserializeOwned + Send + Sync + 'static,
+    {
+        let mut cache = self.properties.lock().unwrap();
+
+        if let Some(props) = cache.get(&TypeId::of::<D>()) {
+            return Ok(props.clone().downcast().unwrap());
+        }
+
+        let props = Arc::new(self.tileset.tile_properties::<D>()?);
+        cache.insert(TypeId::of::<D>(), props.clone());
+
+        Ok(props)
+    }
+}
+
+#[derive(TypeUuid)]
+#[uuid = "5bb4b5e2-4f4b-4b8a-9d1f-0f3c3c1c8e27"]
+pub struct TiledTilesetAsset(pub Arc<TilesetData>);
+
+/// The tilesets parsed so far, keyed by their asset paths. Both the map
+/// and the tileset loaders look here before parsing a `.tsx` file. The
+/// entries are dropped along with their [TiledTilesetAsset].
+#[derive(Resource, Clone, Default)]
+pub struct TilesetCache(Arc<Mutex<HashMap<PathBuf, CachedTileset>>>);
+
+struct CachedTileset {
+    data: Arc<TilesetData>,
+    /// Whether the tileset loader has already used the entry. The next
+    /// load of the same path is a reload.
+    loaded: bool,
+}
+
+impl TilesetCache {
+    pub fn get(&self, path: &Path) -> Option<Arc<TilesetData>> {
+        self.0.lock().unwrap().get(&normalize_path(path)).map(|x| x.data.clone())
+    }
+
+    pub fn insert(&self, path: &Path, data: Arc<TilesetData>) {
+        self.0.lock().unwrap().insert(normalize_path(path), CachedTileset { data, loaded: false });
+    }
+
+    pub fn remove(&self, path: &Path) {
+        self.0.lock().unwrap().remove(&normalize_path(path));
+    }
+
+    /// Finds the data of the tileset, if it has been loaded from a `.tsx`
+    /// file. Tilesets embedded into maps are never cached.
+    pub fn find(&self, tileset: &Arc<Tileset>) -> Option<Arc<TilesetData>> {
+        self.get(&tileset.source)
+            .filter(|data| Arc::ptr_eq(&data.tileset, tileset))
+    }
+
+    /// Hands the tileset loader the tileset a map loader has parsed. Only
+    /// the first load of a path gets it, the rest are reloads of a file
+    /// which might have changed.
+    fn take_for_load(&self, path: &Path) -> Option<Arc<TilesetData>> {
+        let mut cache = self.0.lock().unwrap();
+        let entry = cache.get_mut(&normalize_path(path)).filter(|x| !x.loaded)?;
+        entry.loaded = true;
+
+        Some(entry.data.clone())
+    }
+
+    fn insert_loaded(&self, path: &Path, data: Arc<TilesetData>) {
+        self.0.lock().unwrap().insert(normalize_path(path), CachedTileset { data, loaded: true });
+    }
+}
+
+/// Drops the cached tilesets, once their assets are gone.
+pub fn evict_removed_tilesets(
+    mut events: EventReader<AssetEvent<TiledTilesetAsset>>,
+    asset_server: Res<AssetServer>,
+    cache: Res<TilesetCache>,
+) {
+    for event in events.iter() {
+        if let AssetEvent::Removed { handle } = event {
+            if let Some(path) = asset_server.get_handle_path(handle) {
+                cache.remove(path.path());
+            }
+        }
+    }
+}
+
+/// Lets `tiled` reuse the cached tilesets while loading a map.
+pub(crate) struct ParseCache<'a> {
+    pub tilesets: &'a TilesetCache,
+    pub templates: HashMap<PathBuf, Arc<Template>>,
+}
+
+impl<'a> ResourceCache for ParseCache<'a> {
+    fn get_tileset(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        self.tilesets.get(path.as_ref()).map(|data| data.tileset.clone())
+    }
+
+    fn insert_tileset(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Tileset>) {
+        self.tilesets.insert(path.as_ref(), Arc::new(TilesetData::new(tileset)));
+    }
+
+    fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        self.templates.get(&normalize_path(path.as_ref())).cloned()
+    }
+
+    fn insert_template(&mut self, path: impl AsRef<ResourcePath>, template: Arc<Template>) {
+        self.templates.insert(normalize_path(path.as_ref()), template);
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TiledTilesetLoader {
+    cache: TilesetCache,
+}
+
+impl TiledTilesetLoader {
+    pub fn new(cache: TilesetCache) -> Self {
+        TiledTilesetLoader { cache }
+    }
+}
+
+impl AssetLoader for TiledTilesetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let path = load_context.path().to_owned();
+
+            // The map, which depends on the tileset, has most likely parsed
+            // it already. On a reload the file gets parsed again.
+            let data = match self.cache.take_for_load(&path) {
+                Some(data) => data,
+                None => {
+                    let tileset = parse_with_asset_io(load_context, &path, bytes, |reader| {
+                        tiled::Loader::with_reader(reader).load_tsx_tileset(&path)
+                    }).await?;
+                    let data = Arc::new(TilesetData::new(Arc::new(tileset)));
+                    self.cache.insert_loaded(&path, data.clone());
+
+                    data
+                },
+            };
+
+            let loaded_asset = LoadedAsset::new(TiledTilesetAsset(data.clone()))
+                .with_dependencies(data.dependencies());
+            load_context.set_default_asset(loaded_asset);
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] { &["tsx"] }
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::tiles::TilePos;
+use bevy_tiled::TileBounds;
+use common::MemoryFiles;
+
+const FINITE_MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.8" orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="32" tileheight="32" infinite="0" nextlayerid="2" nextobjectid="1">
+ <tileset firstgid="1" name="floor" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+  <image source="floor.png" width="32" height="32"/>
+ </tileset>
+ <layer id="1" name="floor" width="3" height="2">
+  <data encoding="csv">
+0,1,0,
+0,0,0
+</data>
+ </layer>
+</map>
+"#;
+
+fn load(map: String) -> tiled::Map {
+    tiled::Loader::with_reader(MemoryFiles::new(&[("maps/bounds.tmx", &map)]))
+        .load_tmx_map("maps/bounds.tmx")
+        .unwrap()
+}
+
+/// An infinite map with a tile at each of `tiles`, all in one layer.
+fn infinite_map(tiles: &[(i32, i32)]) -> String {
+    const SIZE: i32 = 16;
+    let mut chunks: Vec<((i32, i32), Vec<u32>)> = Vec::new();
+    for &(x, y) in tiles {
+        let corner = (x.div_euclid(SIZE) * SIZE, y.div_euclid(SIZE) * SIZE);
+        let idx = match chunks.iter().position(|(pos, _)| *pos == corner) {
+            Some(idx) => idx,
+            None => {
+                chunks.push((corner, vec![0; (SIZE * SIZE) as usize]));
+                chunks.len() - 1
+            },
+        };
+        chunks[idx].1[(y.rem_euclid(SIZE) * SIZE + x.rem_euclid(SIZE)) as usize] = 1;
+    }
+
+    let chunks: String = chunks.iter()
+        .map(|((x, y), data)| format!(
+            "   <chunk x=\"{x}\" y=\"{y}\" width=\"{SIZE}\" height=\"{SIZE}\">\n{}\n</chunk>\n",
+            data.iter().map(u32::to_string).collect::<Vec<_>>().join(","),
+        ))
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.8" orientation="orthogonal" renderorder="right-down" width="30" height="20" tilewidth="32" tileheight="32" infinite="1" nextlayerid="2" nextobjectid="1">
+ <tileset firstgid="1" name="floor" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+  <image source="floor.png" width="32" height="32"/>
+ </tileset>
+ <layer id="1" name="floor" width="30" height="20">
+  <data encoding="csv">
+{chunks}  </data>
+ </layer>
+</map>
+"#
+    )
+}
+
+#[test]
+fn finite_maps_are_bounded_by_their_size() {
+    let bounds = TileBounds::of_map(&load(FINITE_MAP.to_owned()));
+
+    assert_eq!(bounds, TileBounds { min: IVec2::ZERO, size: UVec2::new(3, 2) });
+    // `y` gets flipped, so the top row is the last one
+    assert_eq!(bounds.tile_pos((0, 0)), Some(TilePos { x: 0, y: 1 }));
+    assert_eq!(bounds.tile_pos((2, 1)), Some(TilePos { x: 2, y: 0 }));
+}
+
+#[test]
+fn infinite_maps_are_bounded_by_their_tiles() {
+    let bounds = TileBounds::of_map(&load(infinite_map(&[(-3, -2), (5, 1), (0, 0)])));
+
+    assert_eq!(bounds, TileBounds { min: IVec2::new(-3, -2), size: UVec2::new(9, 4) });
+    assert_eq!(bounds.tile_pos((-3, -2)), Some(TilePos { x: 0, y: 3 }));
+    assert_eq!(bounds.tile_pos((5, 1)), Some(TilePos { x: 8, y: 0 }));
+    assert_eq!(bounds.tile_pos((0, 0)), Some(TilePos { x: 3, y: 1 }));
+}
+
+#[test]
+fn positions_out_of_bounds_have_no_tile() {
+    let finite = TileBounds::of_map(&load(FINITE_MAP.to_owned()));
+    let infinite = TileBounds::of_map(&load(infinite_map(&[(-3, -2), (5, 1)])));
+
+    for pos in [(-1, 0), (3, 0), (0, -1), (0, 2)] {
+        assert_eq!(finite.tile_pos(pos), None, "{pos:?}");
+    }
+    for pos in [(-4, -2), (6, 1), (-3, -3), (5, 2)] {
+        assert_eq!(infinite.tile_pos(pos), None, "{pos:?}");
+    }
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_tiled::{BasicDeserBuilder, CallbackSelector, TileBuilder, TileBuilderRegistry, TilesetMatch};
+use common::MemoryFiles;
+use serde::Deserialize;
+
+const TILESET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.8" name="floor" tilewidth="32" tileheight="32" tilecount="2" columns="2">
+ <image source="../tiles/floor.png" width="64" height="32"/>
+ <tile id="1" type="Exit">
+  <properties>
+   <property name="lit" type="bool" value="true"/>
+  </properties>
+ </tile>
+</tileset>
+"#;
+
+#[derive(Clone, Component, Deserialize)]
+struct Exit {
+    lit: bool,
+}
+
+#[test]
+fn registered_builders_are_picked_by_name_or_class() {
+    let tileset = tiled::Loader::with_reader(MemoryFiles::new(&[("tilesets/floor.tsx", TILESET)]))
+        .load_tsx_tileset("tilesets/floor.tsx")
+        .unwrap();
+    let builder = || -> Box<dyn TileBuilder> { Box::new(BasicDeserBuilder::<Exit>::new(|_| ())) };
+
+    let empty = TileBuilderRegistry::default();
+    let err = empty.selector(None).select(&tileset).err().unwrap();
+    assert!(err.to_string().contains("\"floor\""), "{err}");
+
+    let mut by_name = TileBuilderRegistry::default();
+    by_name.add("floor", builder);
+    assert!(by_name.selector(None).select(&tileset).is_ok());
+
+    let mut by_class = TileBuilderRegistry::default();
+    by_class.add(TilesetMatch::Class("Door".to_owned()), builder);
+    assert!(by_class.selector(None).select(&tileset).is_err());
+    by_class.add(TilesetMatch::Class("Exit".to_owned()), builder);
+    assert!(by_class.selector(None).select(&tileset).is_ok());
+}
@@ -0,0 +1,64 @@
+//! Fixtures shared by the tests.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::{AssetIo, AssetIoError, BoxedFuture, FileType, Metadata};
+
+/// Serves files from memory, by their paths. Works both as a reader for
+/// `tiled` and as the asset IO, the same way the web build gets the
+/// assets over HTTP rather than from a file system.
+pub struct MemoryFiles(HashMap<PathBuf, Vec<u8>>);
+
+impl MemoryFiles {
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        MemoryFiles(files.iter()
+            .map(|(path, data)| (PathBuf::from(path), data.as_bytes().to_vec()))
+            .collect())
+    }
+
+    fn get(&self, path: &Path) -> Option<&[u8]> {
+        self.0.get(path).map(Vec::as_slice)
+    }
+}
+
+impl tiled::ResourceReader for MemoryFiles {
+    type Resource = Cursor<Vec<u8>>;
+    type Error = std::io::Error;
+
+    fn read_from(&mut self, path: &Path) -> Result<Self::Resource, Self::Error> {
+        self.get(path)
+            .map(|data| Cursor::new(data.to_vec()))
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("{path:?}")))
+    }
+}
+
+impl AssetIo for MemoryFiles {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move {
+            self.get(path)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| AssetIoError::NotFound(path.to_owned()))
+        })
+    }
+
+    fn read_directory(&self, path: &Path) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        Err(AssetIoError::NotFound(path.to_owned()))
+    }
+
+    fn get_metadata(&self, path: &Path) -> Result<Metadata, AssetIoError> {
+        match self.0.contains_key(path) {
+            true => Ok(Metadata::new(FileType::File)),
+            false => Err(AssetIoError::NotFound(path.to_owned())),
+        }
+    }
+
+    fn watch_path_for_changes(&self, _to_watch: &Path, _to_reload: Option<PathBuf>) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,427 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bevy::asset::{AssetPath, AssetPlugin, LoadState};
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::{TilemapTexture, TilePos, TileStorage};
+use bevy_ecs_tilemap_cpu_anim::CPUTileAnimation;
+use bevy_tiled::{
+    deserialize_asset_handle,
+    evict_removed_tilesets,
+    apply_parallax,
+    LayerImages,
+    MapParser,
+    SimpleCallbackSelector,
+    TileBuilder,
+    TiledMap,
+    TiledMapLoader,
+    TiledParallax,
+    TiledTileset,
+    TiledTilesetAsset,
+    TiledTilesetLoader,
+    TilesetAnimations,
+    TilesetCache,
+    TilesetData,
+};
+use common::MemoryFiles;
+use serde::Deserialize;
+
+const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.8" orientation="orthogonal" renderorder="right-down" width="2" height="1" tilewidth="32" tileheight="32" infinite="0" nextlayerid="2" nextobjectid="1">
+ <tileset firstgid="1" source="../tilesets/floor.tsx"/>
+ <layer id="1" name="floor" width="2" height="1">
+  <data encoding="csv">
+1,2
+</data>
+ </layer>
+</map>
+"#;
+
+const TILESET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.8" name="floor" tilewidth="32" tileheight="32" tilecount="2" columns="2">
+ <image source="../tiles/floor.png" width="64" height="32"/>
+ <tile id="1">
+  <properties>
+   <property name="kind" value="exit"/>
+  </properties>
+ </tile>
+</tileset>
+"#;
+
+const TILESET_WITH_FILES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.8" name="floor" tilewidth="32" tileheight="32" tilecount="2" columns="2">
+ <image source="../tiles/floor.png" width="64" height="32"/>
+ <tile id="1" type="Exit">
+  <properties>
+   <property name="glow" type="file" value="../tiles/glow.png"/>
+  </properties>
+ </tile>
+</tileset>
+"#;
+
+/// A map with a layer, which uses two tilesets.
+const MIXED_MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.8" orientation="orthogonal" renderorder="right-down" width="3" height="1" tilewidth="32" tileheight="32" infinite="0" nextlayerid="2" nextobjectid="1">
+ <tileset firstgid="1" source="../tilesets/floor.tsx"/>
+ <tileset firstgid="3" source="../tilesets/wall.tsx"/>
+ <layer id="1" name="tiles" width="3" height="1">
+  <data encoding="csv">
+1,3,2
+</data>
+ </layer>
+</map>
+"#;
+
+const WALL_TILESET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.8" name="wall" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+ <image source="../tiles/wall.png" width="32" height="32"/>
+</tileset>
+"#;
+
+/// A 3x2 map with image layers: a repeated one, a single image and one
+/// with parallax.
+const IMAGE_MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.8" orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="32" tileheight="32" infinite="0" nextlayerid="4" nextobjectid="1">
+ <imagelayer id="1" name="sky" offsetx="10" offsety="-5" repeatx="1" repeaty="1">
+  <image source="../tiles/sky.png" width="40" height="30"/>
+ </imagelayer>
+ <imagelayer id="2" name="sun" offsetx="10" offsety="-5">
+  <image source="../tiles/sun.png" width="40" height="30"/>
+ </imagelayer>
+ <imagelayer id="3" name="hills" parallaxx="0.5" parallaxy="0">
+  <image source="../tiles/hills.png" width="96" height="64"/>
+ </imagelayer>
+</map>
+"#;
+
+/// Loads the maps from the files one after another, the way the levels
+/// get loaded in the game.
+fn load_maps(files: &[(&str, &'static str)], maps: &[&str]) -> (App, Vec<(Handle<TiledMap>, LoadState)>) {
+    let mut app = App::new();
+    let io = MemoryFiles::new(files);
+    let cache = TilesetCache::default();
+
+    app
+        .add_plugins(MinimalPlugins)
+        .insert_resource(AssetServer::new(io))
+        .add_plugin(AssetPlugin::default())
+        .add_asset::<TiledMap>()
+        .add_asset::<TiledTilesetAsset>()
+        .add_asset_loader(TiledMapLoader::new(cache.clone()))
+        .add_asset_loader(TiledTilesetLoader::new(cache.clone()))
+        .insert_resource(cache)
+        .add_system(evict_removed_tilesets);
+
+    let results = maps.iter()
+        .map(|path| {
+            let handle: Handle<TiledMap> = app.world.resource::<AssetServer>().load(*path);
+            let start = Instant::now();
+
+            loop {
+                app.update();
+
+                let state = app.world.resource::<AssetServer>().get_load_state(&handle);
+                if matches!(state, LoadState::Loaded | LoadState::Failed) {
+                    return (handle, state);
+                }
+
+                assert!(start.elapsed() < Duration::from_secs(5), "{path} took too long to load");
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        })
+        .collect();
+
+    (app, results)
+}
+
+#[test]
+fn external_tilesets_load_through_the_asset_io() {
+    let (app, maps) = load_maps(
+        &[
+            ("maps/level.tmx", MAP),
+            ("tilesets/floor.tsx", TILESET),
+        ],
+        &["maps/level.tmx"],
+    );
+    let (handle, state) = &maps[0];
+    assert_eq!(*state, LoadState::Loaded);
+
+    let map = app.world.resource::<Assets<TiledMap>>().get(handle).unwrap();
+    let tileset = &map.map.tilesets()[0];
+    assert_eq!(tileset.name, "floor");
+    assert!(tileset.get_tile(1).unwrap().properties.contains_key("kind"));
+
+    match &map.tilesets[0].source {
+        TiledTileset::Image(path) => assert_eq!(path.path(), Path::new("tiles/floor.png")),
+        x => panic!("Expected a single image tileset, got {x:?}"),
+    }
+}
+
+#[test]
+fn maps_share_their_tilesets() {
+    let (app, maps) = load_maps(
+        &[
+            ("maps/level.tmx", MAP),
+            ("maps/other.tmx", MAP),
+            ("tilesets/floor.tsx", TILESET),
+        ],
+        &["maps/level.tmx", "maps/other.tmx"],
+    );
+    let assets = app.world.resource::<Assets<TiledMap>>();
+    let level = assets.get(&maps[0].0).unwrap();
+    let other = assets.get(&maps[1].0).unwrap();
+
+    assert!(Arc::ptr_eq(&level.tilesets[0], &other.tilesets[0]));
+    assert!(Arc::ptr_eq(&level.map.tilesets()[0], &other.map.tilesets()[0]));
+}
+
+#[test]
+fn missing_tilesets_fail_the_load() {
+    let (_, maps) = load_maps(&[("maps/level.tmx", MAP)], &["maps/level.tmx"]);
+
+    assert_eq!(maps[0].1, LoadState::Failed);
+}
+
+#[derive(Clone, Component, Deserialize)]
+struct Exit {
+    #[serde(deserialize_with = "deserialize_asset_handle")]
+    glow: Handle<Image>,
+}
+
+#[test]
+fn file_properties_become_dependencies_and_handles() {
+    let (app, maps) = load_maps(
+        &[
+            ("maps/level.tmx", MAP),
+            ("tilesets/floor.tsx", TILESET_WITH_FILES),
+        ],
+        &["maps/level.tmx"],
+    );
+    let map = app.world.resource::<Assets<TiledMap>>().get(&maps[0].0).unwrap();
+    let glow = AssetPath::new(PathBuf::from("tiles/glow.png"), None);
+
+    assert_eq!(map.tilesets[0].files, vec![glow.clone()]);
+    assert!(map.tilesets[0].dependencies().contains(&glow));
+
+    let props = map.tilesets[0].properties::<Exit>().unwrap();
+    assert_eq!(props[&1].glow, Handle::weak(glow.into()));
+}
+
+/// Runs the app until `done`, which should happen within a few frames.
+fn update_until(app: &mut App, what: &str, mut done: impl FnMut(&mut App) -> bool) {
+    let start = Instant::now();
+
+    while !done(app) {
+        assert!(start.elapsed() < Duration::from_secs(5), "{what} took too long");
+        app.update();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn reloaded_tilesets_are_parsed_again() {
+    let (mut app, maps) = load_maps(
+        &[
+            ("maps/level.tmx", MAP),
+            ("tilesets/floor.tsx", TILESET),
+        ],
+        &["maps/level.tmx"],
+    );
+    let path = Path::new("tilesets/floor.tsx");
+    let loaded = app.world.resource::<Assets<TiledMap>>().get(&maps[0].0).unwrap().tilesets[0].clone();
+    assert!(Arc::ptr_eq(&app.world.resource::<TilesetCache>().get(path).unwrap(), &loaded));
+
+    app.world.resource::<AssetServer>().reload_asset(path);
+    update_until(&mut app, "The reload", |app| {
+        !Arc::ptr_eq(&app.world.resource::<TilesetCache>().get(path).unwrap(), &loaded)
+    });
+
+    // The maps loaded from now on get the new tileset
+    let (_, reloaded) = app.world.resource::<Assets<TiledTilesetAsset>>().iter().next().unwrap();
+    assert!(Arc::ptr_eq(&app.world.resource::<TilesetCache>().get(path).unwrap(), &reloaded.0));
+}
+
+#[test]
+fn removed_tilesets_leave_the_cache() {
+    let (mut app, _) = load_maps(
+        &[
+            ("maps/level.tmx", MAP),
+            ("tilesets/floor.tsx", TILESET),
+        ],
+        &["maps/level.tmx"],
+    );
+    let path = Path::new("tilesets/floor.tsx");
+    let handle: Handle<TiledTilesetAsset> = app.world.resource::<AssetServer>().load(path);
+    update_until(&mut app, "The tileset", |app| {
+        app.world.resource::<Assets<TiledTilesetAsset>>().contains(&handle)
+    });
+    assert!(app.world.resource::<TilesetCache>().get(path).is_some());
+
+    drop(handle);
+    update_until(&mut app, "The removal", |app| {
+        app.world.resource::<TilesetCache>().get(path).is_none()
+    });
+}
+
+/// Marks the tilemaps with the index of their tileset.
+#[derive(Component)]
+struct TilesetTag(usize);
+
+struct TagBuilder;
+
+impl TileBuilder for TagBuilder {
+    fn process_tileset(&mut self, _set_id: usize, _tileset: &TilesetData) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn build(&mut self, _set_id: usize, _id: u32, _cmds: &mut EntityCommands) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn finish_layer(&mut self, set_id: usize, cmds: &mut EntityCommands) -> anyhow::Result<()> {
+        cmds.insert(TilesetTag(set_id));
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+struct SpawnedMap(Handle<TiledMap>);
+
+fn spawn_map(
+    mut commands: Commands,
+    map: Res<SpawnedMap>,
+    maps: Res<Assets<TiledMap>>,
+    images: Res<Assets<Image>>,
+    mut animations: ResMut<Assets<CPUTileAnimation>>,
+) {
+    let map = maps.get(&map.0).unwrap();
+    let texture_data: Vec<_> = map.tilesets.iter()
+        .map(|data| (data.indexing.clone(), TilemapTexture::Single(default())))
+        .collect();
+    let animations = TilesetAnimations::from_map(&map.map, &texture_data, &mut animations);
+    let layer_images = LayerImages::from_map(map, &images);
+    let mut builder = TagBuilder;
+
+    MapParser::new(
+        &mut commands,
+        SimpleCallbackSelector { pool: [&mut builder], picker: |_| 0 },
+        &texture_data,
+        &animations,
+        &layer_images,
+    )
+    .parse_map(map)
+    .unwrap();
+}
+
+/// Runs a system once, outside of the schedule.
+fn run_once<Params>(world: &mut World, system: impl IntoSystem<(), (), Params>) {
+    let mut system = IntoSystem::into_system(system);
+    system.initialize(world);
+    system.run((), world);
+    system.apply_buffers(world);
+}
+
+/// Loads the map and spawns it with [MapParser].
+fn parse_map(files: &[(&str, &'static str)], map: &str) -> App {
+    let (mut app, maps) = load_maps(files, &[map]);
+    app
+        .add_asset::<Image>()
+        .add_asset::<CPUTileAnimation>()
+        .insert_resource(SpawnedMap(maps[0].0.clone()));
+    run_once(&mut app.world, spawn_map);
+
+    app
+}
+
+/// The children of the layer with the name.
+fn layer_children(world: &mut World, layer: &str) -> (Entity, Vec<Entity>) {
+    world.query::<(Entity, &Name, &Children)>()
+        .iter(world)
+        .find(|(_, name, _)| name.as_str() == layer)
+        .map(|(layer, _, children)| (layer, children.to_vec()))
+        .unwrap()
+}
+
+#[test]
+fn layers_get_a_tilemap_per_tileset() {
+    let mut app = parse_map(
+        &[
+            ("maps/mixed.tmx", MIXED_MAP),
+            ("tilesets/floor.tsx", TILESET),
+            ("tilesets/wall.tsx", WALL_TILESET),
+        ],
+        "maps/mixed.tmx",
+    );
+    let world = &mut app.world;
+    let (layer, children) = layer_children(world, "tiles");
+    assert!(world.get::<TileStorage>(layer).is_none());
+
+    let tilemaps: Vec<_> = children.iter()
+        .map(|&child| (
+            world.get::<Name>(child).unwrap().as_str(),
+            world.get::<TilesetTag>(child).unwrap().0,
+        ))
+        .collect();
+    assert_eq!(tilemaps, [("floor", 0), ("wall", 1)]);
+
+    // The lookups of the gameplay go through all the tilemaps
+    let tileset_at = |world: &mut World, x| world.query::<(&TileStorage, &TilesetTag)>()
+        .iter(world)
+        .filter_map(|(storage, tag)| storage.get(&TilePos { x, y: 0 }).map(|_| tag.0))
+        .collect::<Vec<_>>();
+    assert_eq!(tileset_at(world, 0), [0]);
+    assert_eq!(tileset_at(world, 1), [1]);
+    assert_eq!(tileset_at(world, 2), [0]);
+}
+
+/// The positions of the images of the layer, relative to the top left
+/// corner of the map and with `y` growing downwards, like in `Tiled`.
+fn image_positions(world: &mut World, layer: &str) -> Vec<(i32, i32)> {
+    // The tilemaps are centered on their bottom left tile, which puts the
+    // top left corner of this map at (-16, 48)
+    let mut positions: Vec<_> = layer_children(world, layer).1.iter()
+        .map(|&image| world.get::<Transform>(image).unwrap().translation)
+        .map(|pos| ((pos.x + 16.0) as i32, (48.0 - pos.y) as i32))
+        .collect();
+    positions.sort();
+
+    positions
+}
+
+#[test]
+fn repeated_images_cover_the_map() {
+    let mut app = parse_map(&[("maps/images.tmx", IMAGE_MAP)], "maps/images.tmx");
+
+    // The map is 96x64, so with the offset it takes 4 columns and 3 rows
+    // of the 40x30 image to cover it
+    let expected: Vec<_> = [-30, 10, 50, 90].into_iter()
+        .flat_map(|x| [-5, 25, 55].map(|y| (x, y)))
+        .collect();
+    assert_eq!(image_positions(&mut app.world, "sky"), expected);
+    assert_eq!(image_positions(&mut app.world, "sun"), [(10, -5)]);
+}
+
+#[test]
+fn parallax_layers_follow_the_camera() {
+    let mut app = parse_map(&[("maps/images.tmx", IMAGE_MAP)], "maps/images.tmx");
+    app.world.spawn(Camera2dBundle {
+        transform: Transform::from_xyz(100.0, 40.0, 0.0),
+        ..default()
+    });
+    run_once(&mut app.world, apply_parallax);
+
+    let world = &mut app.world;
+    let (sky, _) = layer_children(world, "sky");
+    let (hills, _) = layer_children(world, "hills");
+    assert!(world.get::<TiledParallax>(sky).is_none());
+    assert_eq!(world.get::<Transform>(sky).unwrap().translation, Vec3::ZERO);
+
+    // Half as fast horizontally and not at all vertically
+    let parallax = world.get::<TiledParallax>(hills).unwrap();
+    assert_eq!(parallax.factor, Vec2::new(0.5, 0.0));
+    assert_eq!(world.get::<Transform>(hills).unwrap().translation, Vec3::new(50.0, 40.0, 2.0));
+}
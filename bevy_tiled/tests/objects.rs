@@ -0,0 +1,81 @@
+mod common;
+
+use bevy_ecs_tilemap::tiles::TilePos;
+use bevy_tiled::{MapObject, TileBounds};
+use common::MemoryFiles;
+use tiled::LayerType;
+
+/// A 3x2 map, whose objects are named after the tile they should be in.
+const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.8" orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="32" tileheight="32" infinite="0" nextlayerid="3" nextobjectid="8">
+ <tileset firstgid="1" name="floor" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+  <image source="floor.png" width="32" height="32"/>
+ </tileset>
+ <layer id="1" name="floor" width="3" height="2">
+  <data encoding="csv">
+1,1,1,
+1,1,1
+</data>
+ </layer>
+ <objectgroup id="2" name="objects">
+  <object id="1" name="1,1 inside" x="48" y="16">
+   <point/>
+  </object>
+  <object id="2" name="1,0 top edge" x="32" y="32">
+   <point/>
+  </object>
+  <object id="3" name="0,0 tile" gid="1" x="0" y="64" width="32" height="32"/>
+  <object id="4" name="2,1 rectangle" x="64" y="0" width="48" height="48"/>
+  <object id="5" name="right" x="96" y="16">
+   <point/>
+  </object>
+  <object id="6" name="above" x="16" y="-8">
+   <point/>
+  </object>
+  <object id="7" name="bottom edge" x="16" y="64">
+   <point/>
+  </object>
+ </objectgroup>
+</map>
+"#;
+
+/// The tiles of the objects, by their names.
+fn object_tiles() -> Vec<(String, Option<TilePos>)> {
+    let map = tiled::Loader::with_reader(MemoryFiles::new(&[("maps/objects.tmx", MAP)]))
+        .load_tmx_map("maps/objects.tmx")
+        .unwrap();
+    let bounds = TileBounds::of_map(&map);
+    let objects = map.layers()
+        .find_map(|layer| match layer.layer_type() {
+            LayerType::ObjectLayer(objects) => Some(objects),
+            _ => None,
+        })
+        .unwrap();
+
+    objects.objects()
+        .map(|object| {
+            let object = MapObject::new(&object, &map, &bounds);
+            (object.name().to_owned(), object.tile_pos())
+        })
+        .collect()
+}
+
+#[test]
+fn objects_are_in_the_tile_of_their_corner() {
+    let tiles = object_tiles();
+
+    for (name, tile) in &tiles[..4] {
+        let (x, y) = name.split_once(' ').unwrap().0.split_once(',').unwrap();
+        let expected = TilePos { x: x.parse().unwrap(), y: y.parse().unwrap() };
+        assert_eq!(*tile, Some(expected), "{name}");
+    }
+}
+
+#[test]
+fn objects_outside_of_the_map_have_no_tile() {
+    let tiles = object_tiles();
+
+    for (name, tile) in &tiles[4..] {
+        assert_eq!(*tile, None, "{name}");
+    }
+}
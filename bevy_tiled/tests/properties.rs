@@ -0,0 +1,204 @@
+use bevy_tiled::{TilePropertyDes, TilePropertyDeserError};
+use serde::Deserialize;
+use tiled::{Properties, PropertyValue};
+
+fn props<const N: usize>(props: [(&str, PropertyValue); N]) -> Properties {
+    props.into_iter()
+        .map(|(name, value)| (name.to_owned(), value))
+        .collect()
+}
+
+fn class<const N: usize>(property_type: &str, members: [(&str, PropertyValue); N]) -> PropertyValue {
+    PropertyValue::ClassValue {
+        property_type: property_type.to_owned(),
+        properties: props(members),
+    }
+}
+
+fn frame(id: i32, dur: i32) -> PropertyValue {
+    class("Frame", [
+        ("id", PropertyValue::IntValue(id)),
+        ("dur", PropertyValue::IntValue(dur)),
+    ])
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Frame {
+    id: u32,
+    dur: u32,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Speed(f32);
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Animated {
+    speed: Speed,
+    anim: Vec<Frame>,
+    offset: (i32, i32),
+    target: Option<u32>,
+    transition: Option<Vec<Frame>>,
+}
+
+#[test]
+fn nested_classes_sequences_and_options() {
+    let properties = props([
+        ("speed", PropertyValue::FloatValue(2.0)),
+        ("anim", class("FrameList", [("0", frame(3, 100)), ("1", frame(4, 150))])),
+        ("offset", class("Offset", [("0", PropertyValue::IntValue(-1)), ("1", PropertyValue::IntValue(2))])),
+        ("target", PropertyValue::ObjectValue(7)),
+    ]);
+    let des = TilePropertyDes::new(Some("Animated"), &properties);
+
+    assert_eq!(
+        Animated::deserialize(des).unwrap(),
+        Animated {
+            speed: Speed(2.0),
+            anim: vec![Frame { id: 3, dur: 100 }, Frame { id: 4, dur: 150 }],
+            offset: (-1, 2),
+            target: Some(7),
+            transition: None,
+        },
+    );
+}
+
+#[test]
+fn empty_object_references_are_none() {
+    let properties = props([
+        ("speed", PropertyValue::FloatValue(1.0)),
+        ("anim", class("FrameList", [])),
+        ("offset", class("Offset", [("0", PropertyValue::IntValue(0)), ("1", PropertyValue::IntValue(0))])),
+        ("target", PropertyValue::ObjectValue(0)),
+    ]);
+    let des = TilePropertyDes::new(Some("Animated"), &properties);
+    let animated = Animated::deserialize(des).unwrap();
+
+    assert_eq!(animated.target, None);
+    assert!(animated.anim.is_empty());
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Logic {
+    Button(u32, bool),
+    Frier { on: bool },
+}
+
+#[test]
+fn tuple_variants_read_indexed_properties() {
+    let properties = props([
+        ("0", PropertyValue::IntValue(3)),
+        ("1", PropertyValue::BoolValue(true)),
+    ]);
+    let des = TilePropertyDes::new(Some("Button"), &properties);
+
+    assert_eq!(Logic::deserialize(des).unwrap(), Logic::Button(3, true));
+}
+
+#[test]
+fn errors_name_the_nested_member() {
+    let properties = props([
+        ("speed", PropertyValue::FloatValue(1.0)),
+        ("anim", class("FrameList", [("0", class("Frame", [("id", PropertyValue::StringValue("3".to_owned()))]))])),
+        ("offset", class("Offset", [("0", PropertyValue::IntValue(0)), ("1", PropertyValue::IntValue(0))])),
+    ]);
+    let des = TilePropertyDes::new(Some("Animated"), &properties);
+
+    match Animated::deserialize(des) {
+        Err(err @ TilePropertyDeserError::PropFail { .. }) => {
+            let msg = err.to_string();
+            assert!(msg.contains("\"anim\""), "{msg}");
+            assert!(msg.contains("\"0\""), "{msg}");
+            assert!(msg.contains("\"id\""), "{msg}");
+        },
+        x => panic!("Expected a property error, got {x:?}"),
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct LogicTile {
+    ty: Logic,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Kind {
+    Floor,
+    Button(u8),
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct KindTile {
+    ty: Kind,
+}
+
+#[test]
+fn enum_properties_are_strings_or_variant_classes() {
+    let floor = props([("ty", PropertyValue::StringValue("Floor".to_owned()))]);
+    let button = props([("ty", class("Button", [("0", PropertyValue::IntValue(2))]))]);
+    let frier = props([("ty", class("Frier", [("on", PropertyValue::BoolValue(true))]))]);
+    let tuple = props([("ty", class("Button", [
+        ("0", PropertyValue::IntValue(5)),
+        ("1", PropertyValue::BoolValue(false)),
+    ]))]);
+
+    let kind = |properties| KindTile::deserialize(TilePropertyDes::new(Some("KindTile"), properties));
+    let logic = |properties| LogicTile::deserialize(TilePropertyDes::new(Some("LogicTile"), properties));
+
+    assert_eq!(kind(&floor).unwrap(), KindTile { ty: Kind::Floor });
+    assert_eq!(kind(&button).unwrap(), KindTile { ty: Kind::Button(2) });
+    assert_eq!(logic(&frier).unwrap(), LogicTile { ty: Logic::Frier { on: true } });
+    assert_eq!(logic(&tuple).unwrap(), LogicTile { ty: Logic::Button(5, false) });
+
+    // The data of a variant can't be left out
+    let bare = props([("ty", PropertyValue::StringValue("Button".to_owned()))]);
+    assert!(kind(&bare).is_err());
+    let empty = props([("ty", class("Button", []))]);
+    assert!(kind(&empty).is_err());
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Counts {
+    small: u8,
+    big: u64,
+}
+
+#[test]
+fn unsigned_ints_reject_negative_values() {
+    let valid = props([
+        ("small", PropertyValue::IntValue(200)),
+        ("big", PropertyValue::IntValue(70000)),
+    ]);
+    let des = TilePropertyDes::new(Some("Counts"), &valid);
+    assert_eq!(Counts::deserialize(des).unwrap(), Counts { small: 200, big: 70000 });
+
+    for (name, value) in [("small", -1), ("big", -5)] {
+        let mut invalid = valid.clone();
+        invalid.insert(name.to_owned(), PropertyValue::IntValue(value));
+        let des = TilePropertyDes::new(Some("Counts"), &invalid);
+
+        let msg = Counts::deserialize(des).unwrap_err().to_string();
+        assert!(msg.contains(&format!("{name:?}")), "{msg}");
+        assert!(msg.contains(&format!("found {value}")), "{msg}");
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Sprite<'a> {
+    image: String,
+    #[serde(borrow)]
+    raw: &'a str,
+}
+
+#[test]
+fn only_owned_file_paths_are_resolved() {
+    let properties = props([
+        ("image", PropertyValue::FileValue("../tiles/floor.png".to_owned())),
+        ("raw", PropertyValue::FileValue("../tiles/floor.png".to_owned())),
+    ]);
+    let des = TilePropertyDes::new(Some("Sprite"), &properties)
+        .with_base_dir(Some(std::path::Path::new("tilesets")));
+
+    assert_eq!(
+        Sprite::deserialize(des).unwrap(),
+        Sprite { image: "tiles/floor.png".to_owned(), raw: "../tiles/floor.png" },
+    );
+}
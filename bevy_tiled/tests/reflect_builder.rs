@@ -0,0 +1,79 @@
+mod common;
+
+use std::sync::Arc;
+
+use bevy::ecs::system::CommandQueue;
+use bevy::prelude::*;
+use bevy_tiled::{ReflectTileBuilder, TileBuilder, TilesetData};
+use common::MemoryFiles;
+
+const TILESET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.8" name="doors" tilewidth="32" tileheight="32" tilecount="2" columns="2">
+ <image source="../tiles/doors.png" width="64" height="32"/>
+ <tile id="0" type="Door">
+  <properties>
+   <property name="key" type="int" value="3"/>
+   <property name="locked" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="1" type="Hatch"/>
+</tileset>
+"#;
+
+#[derive(Component, Reflect, Default, Debug, PartialEq)]
+#[reflect(Component)]
+struct Door {
+    key: i32,
+    locked: bool,
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+struct Hatch;
+
+fn load_tileset() -> TilesetData {
+    let tileset = tiled::Loader::with_reader(MemoryFiles::new(&[("tilesets/doors.tsx", TILESET)]))
+        .load_tsx_tileset("tilesets/doors.tsx")
+        .unwrap();
+
+    TilesetData::new(Arc::new(tileset))
+}
+
+#[test]
+fn classes_become_reflected_components() {
+    let mut app = App::new();
+    app.register_type::<Door>().register_type::<Hatch>();
+    let registry = app.world.resource::<AppTypeRegistry>().clone();
+
+    let mut builder = ReflectTileBuilder::new(registry, |_| ());
+    builder.process_tileset(0, &load_tileset()).unwrap();
+
+    let mut queue = CommandQueue::default();
+    let (door, hatch) = {
+        let mut commands = Commands::new(&mut queue, &app.world);
+        let mut spawn = |id| {
+            let mut cmds = commands.spawn_empty();
+            builder.build(0, id, &mut cmds).unwrap();
+            cmds.id()
+        };
+
+        (spawn(0), spawn(1))
+    };
+    queue.apply(&mut app.world);
+
+    assert_eq!(app.world.get::<Door>(door), Some(&Door { key: 3, locked: true }));
+    assert!(app.world.get::<Hatch>(hatch).is_some());
+    assert!(app.world.get::<Door>(hatch).is_none());
+}
+
+#[test]
+fn unregistered_classes_fail_the_tileset() {
+    let mut app = App::new();
+    app.register_type::<Door>();
+    let registry = app.world.resource::<AppTypeRegistry>().clone();
+
+    let mut builder = ReflectTileBuilder::new(registry, |_| ());
+    let err = builder.process_tileset(0, &load_tileset()).unwrap_err();
+
+    assert!(err.to_string().contains("\"Hatch\""), "{err}");
+}
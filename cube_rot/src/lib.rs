@@ -1,8 +1,8 @@
 //use std::num::NonZeroU8;
-use bevy::prelude::*;
-use bevy_ecs_tilemap::prelude::*;
+#[cfg(feature = "bevy")] use bevy::prelude::*;
+#[cfg(feature = "bevy")] use bevy_ecs_tilemap::prelude::*;
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum MoveDirection {
     #[default]
@@ -22,17 +22,25 @@ impl MoveDirection {
         }
     }
 
-    pub fn apply_on_pos(self, TilePos { x, y }: TilePos) -> Option<TilePos> {
+    /// Moves the coordinates one step into the direction. Returns `None`
+    /// if the result would have a negative coordinate.
+    pub fn apply_on_coords(self, (x, y): (u32, u32)) -> Option<(u32, u32)> {
         let (dx, dy) = self.to_offset();
         let (x, y) = (x as i32 + dx, y as i32 + dy);
 
         if x < 0 || y < 0 {
             None
         } else {
-            Some(TilePos { x: x as u32, y: y as u32 })
+            Some((x as u32, y as u32))
         }
     }
 
+    #[cfg(feature = "bevy")]
+    pub fn apply_on_pos(self, TilePos { x, y }: TilePos) -> Option<TilePos> {
+        self.apply_on_coords((x, y)).map(|(x, y)| TilePos { x, y })
+    }
+
+    #[cfg(feature = "bevy")]
     pub fn to_quat(self, t: f32) -> Quat {
         match self {
             Self::Left => Quat::from_rotation_y(-t * std::f32::consts::FRAC_PI_2),
@@ -126,7 +134,7 @@ impl FlatRotation {
 }
 */
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 #[repr(u8)]
 enum BoxSide {
     Up = 0,
@@ -139,6 +147,7 @@ enum BoxSide {
 }
 
 impl BoxSide {
+    #[cfg(feature = "bevy")]
     fn to_quat(self) -> Quat {
         match self {
             Self::Left => Quat::from_rotation_y(-std::f32::consts::FRAC_PI_2),
@@ -153,7 +162,7 @@ impl BoxSide {
 
 /// Represents object rotation using integers, assuming it's always going
 /// to be rotated by 90 degreees.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct DecomposedRotation {
     ortho_rot: u8, // Orthogonal rotation clock-wise. Gets applied first
     flat_rot: BoxSide, // Flat rotation. Gets applied second
@@ -233,6 +242,7 @@ impl DecomposedRotation {
         }
     }
 
+    #[cfg(feature = "bevy")]
     pub fn rot_quat(&self) -> Quat {
         let flat_rot_quat = self.flat_rot.to_quat();
         let ortho_rot_quat = Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2 * self.ortho_rot as f32);
@@ -1,6 +1,7 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem="windows")]
 
 use std::io::Cursor;
+use std::path::Path;
 use bevy::{prelude::*, window::WindowId, winit::WinitWindows};
 use game_lib::LaunchParams;
 use winit::window::Icon;
@@ -58,8 +59,133 @@ enum Commands {
             default_value_t = false,
         )]
         debugging: bool,
+        /// Records the level attempts into the directory
+        #[arg(long = "record-replays")]
+        record_replays: Option<String>,
     },
     Schedule,
+    /// Finds the shortest solution for each of the maps
+    Solve {
+        paths: Vec<String>,
+    },
+    /// Checks the maps for problems without starting the game. Prints
+    /// one JSON object per diagnostic.
+    Validate {
+        paths: Vec<String>,
+    },
+    /// Writes the custom property types of the tiles into the Tiled
+    /// project, so the editor matches the game's types
+    PropertyTypes {
+        #[arg(default_value = "levels.tiled-project")]
+        project: String,
+        /// Only report the types which differ and the tiles of the
+        /// project's tilesets which don't deserialize, then exit with 1
+        /// if there are any
+        #[arg(
+            long = "check",
+            default_value_t = false,
+        )]
+        check: bool,
+    },
+    /// Plays back a recorded level attempt
+    Replay {
+        file: String,
+    },
+    /// Plays the moves on a map without a window and exits with 0 on a
+    /// win, 3 on a death, 4 if the player is stuck, 5 if the moves run out
+    /// and 1 on an error. Use `run --level` to play a map in a window
+    Play {
+        path: String,
+        /// The rolls to make, like `WASD`
+        #[arg(long = "moves")]
+        moves: String,
+    },
+}
+
+fn validate_maps(paths: &[String]) -> bool {
+    use game_lib::{ validate_map_file, Severity };
+
+    let mut ok = true;
+    for path in paths {
+        for diagnostic in validate_map_file(Path::new(path)) {
+            ok &= diagnostic.severity != Severity::Error;
+            println!("{}", serde_json::to_string(&diagnostic).unwrap());
+        }
+    }
+
+    ok
+}
+
+fn solve_maps(paths: &[String]) -> bool {
+    use puzzle_rules::{ solve, format_moves, Solution };
+
+    let mut ok = true;
+    for path in paths {
+        match game_lib::load_level_state(Path::new(path)) {
+            Ok(state) => match solve(&state) {
+                Solution::Found(moves) => println!(
+                    "{path}: par {} {}",
+                    moves.len(),
+                    format_moves(&moves),
+                ),
+                Solution::Unsolvable => println!("{path}: unsolvable"),
+            },
+            Err(e) => {
+                eprintln!("{path}: error: {e:#}");
+                ok = false;
+            },
+        }
+    }
+
+    ok
+}
+
+fn property_types(project: &str, check: bool) -> bool {
+    let path = Path::new(project);
+
+    if !check {
+        return match game_lib::update_project_file(path) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("{project}: error: {e:#}");
+                false
+            },
+        };
+    }
+
+    match game_lib::check_project_file(path) {
+        Ok(drift) => {
+            for line in &drift {
+                println!("{project}: {line}");
+            }
+            drift.is_empty()
+        },
+        Err(e) => {
+            eprintln!("{project}: error: {e:#}");
+            false
+        },
+    }
+}
+
+fn play_map(path: &str, moves: &str) -> i32 {
+    let moves = match puzzle_rules::parse_moves(moves) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("{path}: error: {e}");
+            return 1;
+        },
+    };
+
+    match game_lib::play_headless(Path::new(path), &moves) {
+        Ok(outcome) => {
+            println!("{path}: {outcome:?}");
+            outcome.exit_code()
+        },
+        Err(e) => {
+            eprintln!("{path}: error: {e:#}");
+            1
+        },
+    }
 }
 
 fn main() {
@@ -74,12 +200,15 @@ fn main() {
             level_file, 
             logging, 
             inspector, 
-            debugging 
+            debugging,
+            record_replays,
         }) => {
             let params = LaunchParams {
                 logging: logging || debugging,
                 inspector: inspector || debugging,
                 level_file: level_file.as_deref(),
+                replay: None,
+                replay_dir: record_replays.as_deref(),
             };
             game_lib::app(params)
                 .add_startup_system(set_window_icon)
@@ -90,7 +219,47 @@ fn main() {
                 logging: false, 
                 inspector: false, 
                 level_file: None, 
+                replay: None,
+                replay_dir: None,
             })
         ),
+        Some(Commands::Solve { paths }) => if !solve_maps(&paths) {
+            std::process::exit(1);
+        },
+        Some(Commands::Validate { paths }) => if !validate_maps(&paths) {
+            std::process::exit(1);
+        },
+        Some(Commands::PropertyTypes { project, check }) => if !property_types(&project, check) {
+            std::process::exit(1);
+        },
+        Some(Commands::Replay { file }) => {
+            let replay = match game_lib::Replay::load(Path::new(&file)) {
+                Ok(x) => x,
+                Err(e) => {
+                    eprintln!("{file}: error: {e:#}");
+                    std::process::exit(1);
+                },
+            };
+            if replay.version != game_lib::VERSION {
+                warn!(
+                    "The replay was recorded with version {}, but this is {}. It may play back differently.",
+                    replay.version,
+                    game_lib::VERSION,
+                );
+            }
+
+            let map = replay.map.clone();
+            let params = LaunchParams {
+                logging: false,
+                inspector: false,
+                level_file: Some(&map),
+                replay: Some(replay),
+                replay_dir: None,
+            };
+            game_lib::app(params)
+                .add_startup_system(set_window_icon)
+                .run();
+        },
+        Some(Commands::Play { path, moves }) => std::process::exit(play_map(&path, &moves)),
     }
 }
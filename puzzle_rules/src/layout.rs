@@ -0,0 +1,114 @@
+//! The static part of a level: where the tiles are and what they are.
+
+use std::collections::HashMap;
+
+use crate::{Flip, LogicKind, SideCondition};
+
+/// A tile position. Just like in the game, `y` grows upwards.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pos {
+    pub x: u32,
+    pub y: u32,
+}
+
+impl Pos {
+    pub fn new(x: u32, y: u32) -> Self {
+        Pos { x, y }
+    }
+}
+
+/// A logic tile of the level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogicTile {
+    pub pos: Pos,
+    pub kind: LogicKind,
+    pub flip: Flip,
+}
+
+/// The layout of a level. The layout never changes while the level
+/// is played, so it can be shared between many [LevelState](crate::LevelState)s.
+///
+/// Tiles are addressed by their index, which is the order in which they
+/// were added.
+#[derive(Clone, Debug, Default)]
+pub struct Layout {
+    tiles: Vec<LogicTile>,
+    by_pos: HashMap<Pos, usize>,
+    side_triggers: Vec<(usize, SideCondition)>,
+    button_triggers: Vec<(usize, u8)>,
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a logic tile. A tile which was already placed at the same
+    /// position gets replaced.
+    pub fn add_tile(&mut self, pos: Pos, kind: LogicKind, flip: Flip) -> usize {
+        let tile = LogicTile { pos, kind, flip };
+
+        match self.by_pos.get(&pos) {
+            Some(&idx) => {
+                self.tiles[idx] = tile;
+                idx
+            },
+            None => {
+                self.tiles.push(tile);
+                self.by_pos.insert(pos, self.tiles.len() - 1);
+                self.tiles.len() - 1
+            },
+        }
+    }
+
+    /// Adds a side trigger. Just like in the game, triggers which aren't
+    /// placed over a logic tile are ignored.
+    pub fn add_side_trigger(&mut self, pos: Pos, cond: SideCondition) -> bool {
+        match self.by_pos.get(&pos) {
+            Some(&idx) => {
+                self.side_triggers.push((idx, cond));
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Adds a button trigger. Just like in the game, triggers which aren't
+    /// placed over a logic tile are ignored.
+    pub fn add_button_trigger(&mut self, pos: Pos, button_id: u8) -> bool {
+        match self.by_pos.get(&pos) {
+            Some(&idx) => {
+                self.button_triggers.push((idx, button_id));
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn tiles(&self) -> &[LogicTile] {
+        &self.tiles
+    }
+
+    pub fn tile_index(&self, pos: Pos) -> Option<usize> {
+        self.by_pos.get(&pos).copied()
+    }
+
+    pub fn tile_at(&self, pos: Pos) -> Option<&LogicTile> {
+        self.tile_index(pos).map(|idx| &self.tiles[idx])
+    }
+
+    pub fn side_triggers(&self) -> &[(usize, SideCondition)] {
+        &self.side_triggers
+    }
+
+    pub fn button_triggers(&self) -> &[(usize, u8)] {
+        &self.button_triggers
+    }
+
+    /// Iterates over the positions of all start tiles.
+    pub fn starts(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.tiles.iter()
+            .filter(|tile| tile.kind == LogicKind::Start)
+            .map(|tile| tile.pos)
+    }
+}
@@ -0,0 +1,21 @@
+//! A headless and deterministic model of the game's puzzle rules. The
+//! game resolves every roll of the player with it and only animates the
+//! outcomes, while the tools (solvers, validators, tests) can simulate
+//! whole levels with it without running bevy.
+//!
+//! The player is the only moveable the rules know about. The tiles don't
+//! act on any other moveable the game might spawn.
+
+pub extern crate cube_rot;
+
+pub mod tile;
+pub mod layout;
+pub mod state;
+pub mod solver;
+pub mod notation;
+
+pub use tile::*;
+pub use layout::*;
+pub use state::*;
+pub use solver::*;
+pub use notation::*;
@@ -0,0 +1,43 @@
+//! Text notation for roll sequences. Each roll is written as the key the
+//! player presses for it: `W` is up, `A` is left, `S` is down and `D` is
+//! right.
+
+use cube_rot::MoveDirection;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("Unexpected character {found:?} in the move sequence")]
+pub struct NotationError {
+    pub found: char,
+}
+
+pub fn move_to_char(dir: MoveDirection) -> char {
+    match dir {
+        MoveDirection::Up => 'W',
+        MoveDirection::Left => 'A',
+        MoveDirection::Down => 'S',
+        MoveDirection::Right => 'D',
+    }
+}
+
+pub fn move_from_char(ch: char) -> Result<MoveDirection, NotationError> {
+    match ch.to_ascii_uppercase() {
+        'W' => Ok(MoveDirection::Up),
+        'A' => Ok(MoveDirection::Left),
+        'S' => Ok(MoveDirection::Down),
+        'D' => Ok(MoveDirection::Right),
+        found => Err(NotationError { found }),
+    }
+}
+
+pub fn format_moves(moves: &[MoveDirection]) -> String {
+    moves.iter().copied().map(move_to_char).collect()
+}
+
+/// Parses a move sequence. Whitespace is ignored.
+pub fn parse_moves(s: &str) -> Result<Vec<MoveDirection>, NotationError> {
+    s.chars()
+        .filter(|ch| !ch.is_whitespace())
+        .map(move_from_char)
+        .collect()
+}
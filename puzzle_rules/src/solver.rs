@@ -0,0 +1,84 @@
+//! Exhaustive search for the shortest solution of a level.
+
+use std::collections::{HashMap, VecDeque};
+
+use cube_rot::MoveDirection;
+
+use crate::{LevelState, Status};
+
+const DIRECTIONS: [MoveDirection; 4] = [
+    MoveDirection::Up,
+    MoveDirection::Left,
+    MoveDirection::Down,
+    MoveDirection::Right,
+];
+
+/// The result of [solve].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Solution {
+    /// The shortest sequence of rolls that reaches an exit
+    Found(Vec<MoveDirection>),
+    /// Every reachable state has been visited and none of them is a win
+    Unsolvable,
+}
+
+impl Solution {
+    /// The amount of rolls the solution takes.
+    pub fn par(&self) -> Option<usize> {
+        match self {
+            Solution::Found(moves) => Some(moves.len()),
+            Solution::Unsolvable => None,
+        }
+    }
+}
+
+/// Searches for the shortest sequence of rolls that wins the level. The
+/// search is a breadth-first search over the whole state of the level
+/// (player position, rotation and every tile state), so it either finds
+/// an optimal solution or proves that there is none.
+pub fn solve(start: &LevelState) -> Solution {
+    // Each visited state gets an index. The parents are indexed the same
+    // way and point at the state each one was reached from.
+    let mut seen = HashMap::new();
+    let mut parents: Vec<Option<(usize, MoveDirection)>> = Vec::new();
+    let mut queue = VecDeque::new();
+
+    seen.insert(start.clone(), 0);
+    parents.push(None);
+    queue.push_back((0, start.clone()));
+
+    while let Some((idx, state)) = queue.pop_front() {
+        if state.status() == Status::Won {
+            return Solution::Found(backtrack(&parents, idx));
+        }
+        if state.status() != Status::Playing {
+            continue;
+        }
+
+        for dir in DIRECTIONS {
+            let mut next = state.clone();
+            next.step(dir);
+
+            if !seen.contains_key(&next) {
+                let next_idx = parents.len();
+                seen.insert(next.clone(), next_idx);
+                parents.push(Some((idx, dir)));
+                queue.push_back((next_idx, next));
+            }
+        }
+    }
+
+    Solution::Unsolvable
+}
+
+fn backtrack(parents: &[Option<(usize, MoveDirection)>], mut idx: usize) -> Vec<MoveDirection> {
+    let mut moves = Vec::new();
+
+    while let Some((parent, dir)) = parents[idx] {
+        moves.push(dir);
+        idx = parent;
+    }
+    moves.reverse();
+
+    moves
+}
@@ -0,0 +1,234 @@
+//! The dynamic part of a level and the step function.
+
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use cube_rot::{DecomposedRotation, MoveDirection};
+use thiserror::Error;
+
+use crate::{interaction, Interaction, Layout, Pos};
+
+#[derive(Debug, Error)]
+pub enum LevelError {
+    #[error("The level has no start tile")]
+    NoStart,
+}
+
+/// The player's cube.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Moveable {
+    pub pos: Pos,
+    pub rotation: DecomposedRotation,
+}
+
+impl Moveable {
+    pub fn upper_side(&self) -> u8 {
+        self.rotation.upper_side()
+    }
+}
+
+/// Whether the level is still being played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Status {
+    Playing,
+    /// The player has reached an active exit
+    Won,
+    /// The player has been destroyed by a frier
+    Dead,
+    /// The player is stuck riding conveyors forever
+    Looping,
+}
+
+/// Things that happened during a [LevelState::step].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The roll has been denied, because there is no tile to roll onto
+    Blocked,
+    /// The player has rolled onto a tile and now has `side` facing up
+    Rolled { dir: MoveDirection, to: Pos, side: u8 },
+    /// The player has been pushed onto a tile by a conveyor
+    Slid { dir: MoveDirection, to: Pos },
+    /// The player has been spun by a spinner
+    Spun { clock_wise: bool },
+    /// A logic tile has changed its state
+    TileChanged { pos: Pos, active: bool },
+    ButtonPressed { button_id: u8 },
+    ExitReached,
+    Died,
+    /// The conveyors keep pushing the player around in circles
+    Looped,
+}
+
+/// A level in progress. Equality and hashing only take the dynamic state
+/// into account, so the states are only comparable if they share the
+/// same layout.
+#[derive(Clone, Debug)]
+pub struct LevelState {
+    layout: Arc<Layout>,
+    player: Moveable,
+    active: Vec<bool>,
+    status: Status,
+}
+
+impl LevelState {
+    /// Starts the level. All tiles start inactive and the player spawns
+    /// on the first start tile of the layout.
+    pub fn new(layout: Arc<Layout>) -> Result<Self, LevelError> {
+        let pos = layout.starts().next().ok_or(LevelError::NoStart)?;
+
+        Ok(LevelState {
+            player: Moveable { pos, rotation: DecomposedRotation::new() },
+            active: vec![false; layout.tiles().len()],
+            status: Status::Playing,
+            layout,
+        })
+    }
+
+    pub fn layout(&self) -> &Arc<Layout> {
+        &self.layout
+    }
+
+    pub fn player(&self) -> Moveable {
+        self.player
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// The states of the logic tiles, indexed the same way as
+    /// [Layout::tiles].
+    pub fn tile_states(&self) -> &[bool] {
+        &self.active
+    }
+
+    pub fn is_active_at(&self, pos: Pos) -> Option<bool> {
+        self.layout.tile_index(pos).map(|idx| self.active[idx])
+    }
+
+    /// Rolls the player into a direction and resolves everything that
+    /// happens until the player comes to rest. Does nothing once the level
+    /// is no longer being played.
+    pub fn step(&mut self, dir: MoveDirection) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+        if self.status != Status::Playing {
+            return outcomes;
+        }
+
+        let next = match self.neighbour(self.player.pos, dir) {
+            Some(x) => x,
+            None => {
+                outcomes.push(Outcome::Blocked);
+                return outcomes;
+            },
+        };
+
+        // Side triggers fire as soon as the player starts rolling
+        let layout = self.layout.clone();
+        let rotation = self.player.rotation.rotate_in_dir(dir);
+        let (from, to) = (self.player.upper_side(), rotation.upper_side());
+        for &(idx, cond) in layout.side_triggers() {
+            if let Some(state) = cond.transition(from, to) {
+                self.set_active(idx, state, &mut outcomes);
+            }
+        }
+
+        self.player = Moveable { pos: layout.tiles()[next].pos, rotation };
+        outcomes.push(Outcome::Rolled { dir, to: self.player.pos, side: to });
+        self.interact(next, &mut outcomes);
+
+        outcomes
+    }
+
+    /// Applies the tile interactions, starting at the tile the player has
+    /// just moved onto.
+    fn interact(&mut self, mut idx: usize, outcomes: &mut Vec<Outcome>) {
+        let layout = self.layout.clone();
+        let mut visited = HashSet::new();
+
+        loop {
+            let tile = layout.tiles()[idx];
+            match interaction(tile.kind, self.active[idx], tile.flip, true) {
+                Interaction::None => return,
+                Interaction::PressButton(button_id) => {
+                    self.set_active(idx, true, outcomes);
+                    outcomes.push(Outcome::ButtonPressed { button_id });
+
+                    for &(trigger, id) in layout.button_triggers() {
+                        if id == button_id {
+                            self.set_active(trigger, true, outcomes);
+                        }
+                    }
+                    return;
+                },
+                Interaction::Slide(dir) => {
+                    let next = match self.neighbour(tile.pos, dir) {
+                        Some(x) => x,
+                        None => return,
+                    };
+
+                    // Sliding never changes tile states, so coming back
+                    // to a visited conveyor means we will never stop.
+                    if !visited.insert(idx) {
+                        self.status = Status::Looping;
+                        outcomes.push(Outcome::Looped);
+                        return;
+                    }
+
+                    self.player.pos = layout.tiles()[next].pos;
+                    outcomes.push(Outcome::Slid { dir, to: self.player.pos });
+                    idx = next;
+                },
+                Interaction::Destroy => {
+                    self.status = Status::Dead;
+                    outcomes.push(Outcome::Died);
+                    return;
+                },
+                Interaction::Spin { clock_wise } => {
+                    self.player.rotation = self.player.rotation.rotate_ortho(clock_wise);
+                    outcomes.push(Outcome::Spun { clock_wise });
+                    return;
+                },
+                Interaction::Win => {
+                    self.status = Status::Won;
+                    outcomes.push(Outcome::ExitReached);
+                    return;
+                },
+            }
+        }
+    }
+
+    fn neighbour(&self, pos: Pos, dir: MoveDirection) -> Option<usize> {
+        dir.apply_on_coords((pos.x, pos.y))
+            .and_then(|(x, y)| self.layout.tile_index(Pos { x, y }))
+    }
+
+    fn set_active(&mut self, idx: usize, state: bool, outcomes: &mut Vec<Outcome>) {
+        if self.active[idx] != state {
+            self.active[idx] = state;
+            outcomes.push(Outcome::TileChanged {
+                pos: self.layout.tiles()[idx].pos,
+                active: state,
+            });
+        }
+    }
+}
+
+impl PartialEq for LevelState {
+    fn eq(&self, other: &Self) -> bool {
+        self.player == other.player &&
+        self.status == other.status &&
+        self.active == other.active
+    }
+}
+
+impl Eq for LevelState {}
+
+impl Hash for LevelState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.player.hash(state);
+        self.status.hash(state);
+        self.active.hash(state);
+    }
+}
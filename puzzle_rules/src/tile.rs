@@ -0,0 +1,126 @@
+//! Tile data and the rules of tile interactions.
+
+#[cfg(feature = "bevy")] use bevy::prelude::*;
+#[cfg(feature = "serde")] use serde::Deserialize;
+use cube_rot::MoveDirection;
+
+/// The tile kind. This data type is mapped directly to the ones you can
+/// see in the level editor.
+///
+/// The tile kind dictates what graphics the game should use and how
+/// should the tile behave.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bevy", derive(Component, Reflect))]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub enum LogicKind {
+    /// Once button tiles are activated when interacted with by a player
+    /// and stay activated for the rest of the level, activating every
+    /// trigger with the same button ID.
+    OnceButton(u8),
+    /// Conveyor tiles push the player into the direction they are
+    /// facing towards when they are active.
+    Conveyor,
+    /// Start tiles are pretty much floor tiles. The only thing that makes
+    /// them special is that the player spawns on them.
+    Start,
+    /// Frier tiles destroy the player when it steps on them while they
+    /// are active.
+    Frier,
+    /// Spinner tiles spin the player, that stepped on them, around when
+    /// they are active.
+    Spinner,
+    /// Exit tiles announce that the level has been beaten when the player
+    /// has stepped on them while they are active.
+    Exit,
+    /// Floor tiles do nothing
+    #[default]
+    Floor,
+}
+
+/// A trigger that switches the tile state based off player's upper side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bevy", derive(Component, Reflect))]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub enum SideCondition {
+    /// The tile is expecting an odd number to be player's upper side
+    OnOddSide,
+    /// The tile is expecting an even number to be player's upper side
+    OnEvenSide,
+}
+
+impl SideCondition {
+    pub fn is_active(self, upper_side: u8) -> bool {
+        match self {
+            SideCondition::OnOddSide => upper_side % 2 == 1,
+            SideCondition::OnEvenSide => upper_side % 2 == 0,
+        }
+    }
+
+    /// Computes the new tile state when the player changes its upper side
+    /// `from` one number `to` another. Returns `None` if the state of the
+    /// tile shouldn't be touched.
+    pub fn transition(self, from: u8, to: u8) -> Option<bool> {
+        let next_state = self.is_active(to);
+
+        // Do transition only when the state changes
+        (self.is_active(from) != next_state).then_some(next_state)
+    }
+}
+
+/// Tile flipping flags, as they are stored in the map.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub struct Flip {
+    pub x: bool,
+    pub y: bool,
+    pub d: bool,
+}
+
+impl Flip {
+    /// Returns the direction towards which the tile is facing.
+    pub fn direction(self) -> MoveDirection {
+        MoveDirection::Up.apply_flipping_flags(self.x, self.y, self.d)
+    }
+
+    /// Tells whether the tile is clock-wise or counter-clock-wise oriented.
+    pub fn is_clock_wise(self) -> bool {
+        !(self.x ^ self.y ^ self.d)
+    }
+}
+
+/// What should happen to the player once it has finished moving onto a
+/// tile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Interaction {
+    /// Nothing happens
+    None,
+    /// The tile becomes active and the button with the ID gets pressed
+    PressButton(u8),
+    /// The moveable gets pushed into the direction
+    Slide(MoveDirection),
+    /// The moveable gets destroyed
+    Destroy,
+    /// The moveable gets rotated around its orthogonal axis
+    Spin { clock_wise: bool },
+    /// The moveable has reached the exit
+    Win,
+}
+
+/// Decides what a tile does to a moveable, which has stepped on it.
+pub fn interaction(
+    kind: LogicKind,
+    active: bool,
+    flip: Flip,
+    is_moveable_player: bool,
+) -> Interaction {
+    match kind {
+        LogicKind::OnceButton(button_id) if is_moveable_player && !active =>
+            Interaction::PressButton(button_id),
+        LogicKind::Conveyor if active => Interaction::Slide(flip.direction()),
+        LogicKind::Frier if active => Interaction::Destroy,
+        LogicKind::Spinner if active => Interaction::Spin {
+            clock_wise: flip.is_clock_wise(),
+        },
+        LogicKind::Exit if is_moveable_player && active => Interaction::Win,
+        _ => Interaction::None,
+    }
+}
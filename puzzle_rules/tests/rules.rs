@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use cube_rot::MoveDirection::{self, *};
+use puzzle_rules::*;
+
+fn all_flips() -> impl Iterator<Item = Flip> {
+    (0..8u8).map(|bits| Flip {
+        x: bits & 1 != 0,
+        y: bits & 2 != 0,
+        d: bits & 4 != 0,
+    })
+}
+
+fn facing(dir: MoveDirection) -> Flip {
+    all_flips().find(|flip| flip.direction() == dir).unwrap()
+}
+
+fn spinning(clock_wise: bool) -> Flip {
+    all_flips().find(|flip| flip.is_clock_wise() == clock_wise).unwrap()
+}
+
+/// Lays the tiles out in a row, starting at `0:0`.
+fn row(tiles: &[(LogicKind, Flip)]) -> Layout {
+    let mut layout = Layout::new();
+    for (x, (kind, flip)) in tiles.iter().enumerate() {
+        layout.add_tile(Pos::new(x as u32, 0), *kind, *flip);
+    }
+
+    layout
+}
+
+fn start(layout: Layout) -> LevelState {
+    LevelState::new(Arc::new(layout)).unwrap()
+}
+
+fn plain(kind: LogicKind) -> (LogicKind, Flip) {
+    (kind, Flip::default())
+}
+
+#[test]
+fn levels_need_a_start() {
+    let layout = row(&[plain(LogicKind::Floor)]);
+
+    assert!(matches!(LevelState::new(Arc::new(layout)), Err(LevelError::NoStart)));
+}
+
+#[test]
+fn rolls_change_position_and_side() {
+    let mut state = start(row(&[plain(LogicKind::Start), plain(LogicKind::Floor)]));
+    assert_eq!(state.player().upper_side(), 4);
+
+    assert_eq!(
+        state.step(Right),
+        vec![Outcome::Rolled { dir: Right, to: Pos::new(1, 0), side: 1 }],
+    );
+    assert_eq!(state.player().pos, Pos::new(1, 0));
+    assert_eq!(state.player().upper_side(), 1);
+    assert_eq!(state.status(), Status::Playing);
+}
+
+#[test]
+fn rolls_into_the_void_are_blocked() {
+    let mut state = start(row(&[plain(LogicKind::Start), plain(LogicKind::Floor)]));
+    let before = state.clone();
+
+    assert_eq!(state.step(Left), vec![Outcome::Blocked]);
+    assert_eq!(state.step(Up), vec![Outcome::Blocked]);
+    assert_eq!(state, before);
+}
+
+#[test]
+fn side_triggers_fire_before_the_roll() {
+    let mut layout = row(&[
+        plain(LogicKind::Start),
+        (LogicKind::Conveyor, facing(Right)),
+        plain(LogicKind::Floor),
+    ]);
+    layout.add_side_trigger(Pos::new(1, 0), SideCondition::OnOddSide);
+    let mut state = start(layout);
+
+    assert_eq!(
+        state.step(Right),
+        vec![
+            Outcome::TileChanged { pos: Pos::new(1, 0), active: true },
+            Outcome::Rolled { dir: Right, to: Pos::new(1, 0), side: 1 },
+            Outcome::Slid { dir: Right, to: Pos::new(2, 0) },
+        ],
+    );
+    assert_eq!(state.player().pos, Pos::new(2, 0));
+}
+
+#[test]
+fn inactive_conveyors_are_floor() {
+    let mut layout = row(&[
+        plain(LogicKind::Start),
+        (LogicKind::Conveyor, facing(Right)),
+        plain(LogicKind::Floor),
+    ]);
+    layout.add_side_trigger(Pos::new(1, 0), SideCondition::OnEvenSide);
+    let mut state = start(layout);
+
+    assert_eq!(
+        state.step(Right),
+        vec![Outcome::Rolled { dir: Right, to: Pos::new(1, 0), side: 1 }],
+    );
+    assert_eq!(state.is_active_at(Pos::new(1, 0)), Some(false));
+}
+
+#[test]
+fn conveyors_chain_until_they_loop() {
+    let mut layout = row(&[
+        plain(LogicKind::Start),
+        (LogicKind::Conveyor, facing(Right)),
+        (LogicKind::Conveyor, facing(Left)),
+    ]);
+    layout.add_side_trigger(Pos::new(1, 0), SideCondition::OnOddSide);
+    layout.add_side_trigger(Pos::new(2, 0), SideCondition::OnOddSide);
+    let mut state = start(layout);
+
+    assert_eq!(
+        state.step(Right),
+        vec![
+            Outcome::TileChanged { pos: Pos::new(1, 0), active: true },
+            Outcome::TileChanged { pos: Pos::new(2, 0), active: true },
+            Outcome::Rolled { dir: Right, to: Pos::new(1, 0), side: 1 },
+            Outcome::Slid { dir: Right, to: Pos::new(2, 0) },
+            Outcome::Slid { dir: Left, to: Pos::new(1, 0) },
+            Outcome::Looped,
+        ],
+    );
+    assert_eq!(state.status(), Status::Looping);
+    assert!(state.step(Right).is_empty());
+}
+
+#[test]
+fn frier_destroys_the_player() {
+    let mut layout = row(&[plain(LogicKind::Start), plain(LogicKind::Frier)]);
+    layout.add_side_trigger(Pos::new(1, 0), SideCondition::OnOddSide);
+    let mut state = start(layout);
+
+    assert_eq!(state.step(Right).last(), Some(&Outcome::Died));
+    assert_eq!(state.status(), Status::Dead);
+    assert!(state.step(Left).is_empty());
+}
+
+#[test]
+fn spinner_turns_the_player() {
+    let spun_side = |active| {
+        let mut layout = Layout::new();
+        layout.add_tile(Pos::new(0, 0), LogicKind::Start, Flip::default());
+        layout.add_tile(Pos::new(1, 0), LogicKind::Spinner, spinning(true));
+        layout.add_tile(Pos::new(1, 1), LogicKind::Floor, Flip::default());
+        if active {
+            layout.add_side_trigger(Pos::new(1, 0), SideCondition::OnOddSide);
+        }
+        let mut state = start(layout);
+
+        let outcomes = state.step(Right);
+        assert_eq!(outcomes.contains(&Outcome::Spun { clock_wise: true }), active);
+        state.step(Up);
+
+        state.player().upper_side()
+    };
+
+    assert_eq!(spun_side(true), 4);
+    assert_eq!(spun_side(false), 5);
+}
+
+#[test]
+fn button_activates_its_triggers_once() {
+    let mut layout = row(&[
+        plain(LogicKind::Start),
+        plain(LogicKind::OnceButton(1)),
+        plain(LogicKind::Frier),
+        plain(LogicKind::Frier),
+    ]);
+    layout.add_button_trigger(Pos::new(2, 0), 1);
+    layout.add_button_trigger(Pos::new(3, 0), 2);
+    let mut state = start(layout);
+
+    assert_eq!(
+        state.step(Right),
+        vec![
+            Outcome::Rolled { dir: Right, to: Pos::new(1, 0), side: 1 },
+            Outcome::TileChanged { pos: Pos::new(1, 0), active: true },
+            Outcome::ButtonPressed { button_id: 1 },
+            Outcome::TileChanged { pos: Pos::new(2, 0), active: true },
+        ],
+    );
+    assert_eq!(state.tile_states(), &[false, true, true, false]);
+
+    state.step(Left);
+    assert!(!state.step(Right).contains(&Outcome::ButtonPressed { button_id: 1 }));
+}
+
+#[test]
+fn exit_wins_only_when_active() {
+    let mut layout = row(&[
+        plain(LogicKind::Start),
+        plain(LogicKind::OnceButton(1)),
+        plain(LogicKind::Exit),
+    ]);
+    layout.add_button_trigger(Pos::new(2, 0), 1);
+    let mut state = start(layout);
+
+    state.step(Right);
+    assert_eq!(state.step(Right).last(), Some(&Outcome::ExitReached));
+    assert_eq!(state.status(), Status::Won);
+    assert!(state.step(Left).is_empty());
+
+    let mut state = start(row(&[
+        plain(LogicKind::Start),
+        plain(LogicKind::Floor),
+        plain(LogicKind::Exit),
+    ]));
+    state.step(Right);
+    assert!(!state.step(Right).contains(&Outcome::ExitReached));
+    assert_eq!(state.status(), Status::Playing);
+}
+
+#[test]
+fn side_conditions_only_report_changes() {
+    assert_eq!(SideCondition::OnOddSide.transition(4, 1), Some(true));
+    assert_eq!(SideCondition::OnOddSide.transition(1, 2), Some(false));
+    assert_eq!(SideCondition::OnOddSide.transition(1, 3), None);
+    assert_eq!(SideCondition::OnEvenSide.transition(4, 1), Some(false));
+    assert_eq!(SideCondition::OnEvenSide.transition(2, 6), None);
+}
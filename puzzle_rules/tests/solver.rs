@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use cube_rot::MoveDirection::{self, *};
+use puzzle_rules::*;
+
+const DIRECTIONS: [MoveDirection; 4] = [Up, Left, Down, Right];
+
+fn start(layout: Layout) -> LevelState {
+    LevelState::new(Arc::new(layout)).unwrap()
+}
+
+fn play(state: &LevelState, moves: &[MoveDirection]) -> LevelState {
+    let mut state = state.clone();
+    for dir in moves {
+        state.step(*dir);
+    }
+
+    state
+}
+
+/// A 3x3 room with the start in a corner and the exit in the opposite
+/// one. The exit is active only while the player has an odd side up.
+fn room() -> LevelState {
+    let mut layout = Layout::new();
+    for y in 0..3 {
+        for x in 0..3 {
+            let kind = match (x, y) {
+                (0, 0) => LogicKind::Start,
+                (2, 2) => LogicKind::Exit,
+                _ => LogicKind::Floor,
+            };
+            layout.add_tile(Pos::new(x, y), kind, Flip::default());
+        }
+    }
+    layout.add_side_trigger(Pos::new(2, 2), SideCondition::OnOddSide);
+
+    start(layout)
+}
+
+/// Calls `f` with every sequence of `len` moves.
+fn for_each_sequence(len: usize, f: &mut impl FnMut(&[MoveDirection])) {
+    fn go(moves: &mut Vec<MoveDirection>, len: usize, f: &mut impl FnMut(&[MoveDirection])) {
+        if moves.len() == len {
+            return f(moves);
+        }
+        for dir in DIRECTIONS {
+            moves.push(dir);
+            go(moves, len, f);
+            moves.pop();
+        }
+    }
+
+    go(&mut Vec::new(), len, f)
+}
+
+#[test]
+fn tiny_level_is_solved() {
+    let mut layout = Layout::new();
+    layout.add_tile(Pos::new(0, 0), LogicKind::Start, Flip::default());
+    layout.add_tile(Pos::new(1, 0), LogicKind::OnceButton(1), Flip::default());
+    layout.add_tile(Pos::new(2, 0), LogicKind::Exit, Flip::default());
+    layout.add_button_trigger(Pos::new(2, 0), 1);
+    let state = start(layout);
+
+    let solution = solve(&state);
+    assert_eq!(solution, Solution::Found(vec![Right, Right]));
+    assert_eq!(solution.par(), Some(2));
+}
+
+#[test]
+fn inactive_exit_is_unsolvable() {
+    let mut layout = Layout::new();
+    layout.add_tile(Pos::new(0, 0), LogicKind::Start, Flip::default());
+    layout.add_tile(Pos::new(1, 0), LogicKind::Floor, Flip::default());
+    layout.add_tile(Pos::new(2, 0), LogicKind::Exit, Flip::default());
+
+    let solution = solve(&start(layout));
+    assert_eq!(solution, Solution::Unsolvable);
+    assert_eq!(solution.par(), None);
+}
+
+#[test]
+fn solutions_are_the_shortest() {
+    let state = room();
+    let moves = match solve(&state) {
+        Solution::Found(moves) => moves,
+        Solution::Unsolvable => panic!("The room has a solution"),
+    };
+
+    assert_eq!(play(&state, &moves).status(), Status::Won);
+    for len in 0..moves.len() {
+        for_each_sequence(len, &mut |shorter| {
+            assert_ne!(
+                play(&state, shorter).status(),
+                Status::Won,
+                "{} is shorter than {}",
+                format_moves(shorter),
+                format_moves(&moves),
+            );
+        });
+    }
+}
@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+use bevy::reflect;
+use bevy_pkv::PkvStore;
+use serde::Deserialize;
+
+use crate::level_info::LevelInfo;
+use crate::save::Save;
+use crate::states::MenuAssets;
+use crate::tile::TileEvent;
+
+const TOAST_DURATION: f32 = 3.0f32;
+
+/// What the player has to do to get an achievement. The conditions
+/// are checked against the save, so they also work for the progress made
+/// before the achievement got added.
+#[derive(Clone, Debug, Deserialize)]
+pub enum AchievementCondition {
+    LevelComplete {
+        world: u8,
+        level: u8,
+    },
+    WorldComplete {
+        world: u8,
+    },
+    /// Beat a level in `moves` rolls or less
+    LevelMoves {
+        world: u8,
+        level: u8,
+        moves: u32,
+    },
+    /// Beat a level in `seconds` or less
+    LevelTime {
+        world: u8,
+        level: u8,
+        seconds: f32,
+    },
+    Deaths(u32),
+    Moves(u32),
+    ButtonPresses(u32),
+}
+
+impl AchievementCondition {
+    pub fn is_met(&self, save: &Save, info: &LevelInfo) -> bool {
+        match *self {
+            AchievementCondition::LevelComplete { world, level } =>
+                save.is_complete(world, level),
+            AchievementCondition::WorldComplete { world } =>
+                world < info.world_count() &&
+                (0..info.level_count_in_world(world)).all(|level| save.is_complete(world, level)),
+            AchievementCondition::LevelMoves { world, level, moves } =>
+                matches!(save.record(world, level), Some(x) if x.moves <= moves),
+            AchievementCondition::LevelTime { world, level, seconds } =>
+                matches!(save.record(world, level), Some(x) if x.time <= seconds),
+            AchievementCondition::Deaths(x) => save.counters().deaths >= x,
+            AchievementCondition::Moves(x) => save.counters().moves >= x,
+            AchievementCondition::ButtonPresses(x) => save.counters().button_presses >= x,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Achievement {
+    /// The key the achievement is stored under in the save
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: AchievementCondition,
+}
+
+#[derive(Deserialize, reflect::TypeUuid)]
+#[uuid = "5b1c7f3e-0a4d-4f4e-9a52-3d0b8e6c2f71"]
+pub struct AchievementList {
+    pub achievements: Vec<Achievement>,
+}
+
+/// Sent once the player gets an achievement.
+pub struct AchievementUnlocked {
+    pub name: String,
+}
+
+#[derive(Component)]
+struct AchievementToast(Timer);
+
+fn count_button_presses(
+    mut save: Option<ResMut<Save>>,
+    mut tile_events: EventReader<TileEvent>,
+) {
+    for event in tile_events.iter() {
+        if let (TileEvent::ButtonPressed { .. }, Some(save)) = (event, save.as_mut()) {
+            save.counters_mut().button_presses += 1;
+        }
+    }
+}
+
+fn unlock_achievements(
+    save: Option<ResMut<Save>>,
+    menu_assets: Option<Res<MenuAssets>>,
+    achievement_lists: Res<Assets<AchievementList>>,
+    level_infos: Res<Assets<LevelInfo>>,
+    mut pkv: ResMut<PkvStore>,
+    mut writer: EventWriter<AchievementUnlocked>,
+) {
+    let (mut save, menu_assets) = match (save, menu_assets) {
+        (Some(save), Some(menu_assets)) if save.is_changed() => (save, menu_assets),
+        _ => return,
+    };
+    let (list, info) = match (
+        achievement_lists.get(&menu_assets.achievements),
+        level_infos.get(&menu_assets.level_info),
+    ) {
+        (Some(list), Some(info)) => (list, info),
+        _ => return,
+    };
+
+    let mut unlocked = false;
+    for achievement in &list.achievements {
+        if save.has_achievement(&achievement.id) { continue; }
+        if !achievement.condition.is_met(&save, info) { continue; }
+
+        info!("Achievement unlocked: {}", achievement.id);
+        save.give_achievement(&achievement.id);
+        writer.send(AchievementUnlocked { name: achievement.name.clone() });
+        unlocked = true;
+    }
+
+    if unlocked {
+        save.store(&mut pkv);
+    }
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    menu_assets: Option<Res<MenuAssets>>,
+    mut events: EventReader<AchievementUnlocked>,
+) {
+    let menu_assets = match menu_assets {
+        Some(x) => x,
+        None => return,
+    };
+
+    for (idx, event) in events.iter().enumerate() {
+        commands
+            .spawn((
+                AchievementToast(Timer::from_seconds(TOAST_DURATION, TimerMode::Once)),
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            right: Val::Px(10.0),
+                            top: Val::Px(10.0 + 70.0 * idx as f32),
+                            ..default()
+                        },
+                        padding: UiRect::all(Val::Px(10.0)),
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.15, 0.15, 0.9).into(),
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    "Achievement unlocked",
+                    TextStyle {
+                        font: menu_assets.main_font.clone(),
+                        font_size: 18.0f32,
+                        color: Color::GRAY,
+                    },
+                ));
+                parent.spawn(TextBundle::from_section(
+                    event.name.clone(),
+                    TextStyle {
+                        font: menu_assets.main_font.clone(),
+                        font_size: 28.0f32,
+                        color: Color::WHITE,
+                    },
+                ));
+            });
+    }
+}
+
+fn tick_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toast_q: Query<(Entity, &mut AchievementToast)>,
+) {
+    for (e, mut toast) in toast_q.iter_mut() {
+        toast.0.tick(time.delta());
+        if toast.0.finished() {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+/// Gives out the achievements and shows a notification for each one.
+pub struct AchievementPlugin;
+
+impl Plugin for AchievementPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<AchievementUnlocked>()
+            .add_system(count_button_presses)
+            .add_system_to_stage(CoreStage::PostUpdate, unlock_achievements)
+            .add_system(spawn_toasts)
+            .add_system(tick_toasts);
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use cube_rot::MoveDirection;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+/// How far a stick has to be tilted to count as pressed
+const STICK_THRESHOLD: f32 = 0.5f32;
+
+/// Things the player can ask the game to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Up,
+    Left,
+    Down,
+    Right,
+    Undo,
+    Restart,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Up,
+        Action::Left,
+        Action::Down,
+        Action::Right,
+        Action::Undo,
+        Action::Restart,
+        Action::Pause,
+    ];
+
+    pub const MOVES: [Action; 4] = [
+        Action::Up,
+        Action::Left,
+        Action::Down,
+        Action::Right,
+    ];
+
+    pub fn from_move_direction(dir: MoveDirection) -> Self {
+        match dir {
+            MoveDirection::Up => Action::Up,
+            MoveDirection::Left => Action::Left,
+            MoveDirection::Down => Action::Down,
+            MoveDirection::Right => Action::Right,
+        }
+    }
+
+    pub fn move_direction(self) -> Option<MoveDirection> {
+        match self {
+            Action::Up => Some(MoveDirection::Up),
+            Action::Left => Some(MoveDirection::Left),
+            Action::Down => Some(MoveDirection::Down),
+            Action::Right => Some(MoveDirection::Right),
+            _ => None,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Action::Up => "Roll up",
+            Action::Left => "Roll left",
+            Action::Down => "Roll down",
+            Action::Right => "Roll right",
+            Action::Undo => "Undo",
+            Action::Restart => "Restart",
+            Action::Pause => "Pause",
+        }
+    }
+}
+
+/// A physical input that can trigger an action. The gamepad bindings
+/// work for any connected gamepad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    GamepadButton(GamepadButtonType),
+    /// Tilting a stick along the axis. `positive` tells the direction.
+    GamepadStick {
+        axis: GamepadAxisType,
+        positive: bool,
+    },
+}
+
+impl Binding {
+    fn is_same_device(&self, other: &Binding) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// Maps the inputs to the actions. An action can have any amount of
+/// bindings.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bindings(Vec<(Action, Binding)>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use GamepadButtonType as Button;
+        use GamepadAxisType as Axis;
+        use Binding::*;
+
+        Bindings(vec![
+            (Action::Up, Key(KeyCode::W)),
+            (Action::Up, Key(KeyCode::Up)),
+            (Action::Up, GamepadButton(Button::DPadUp)),
+            (Action::Up, GamepadStick { axis: Axis::LeftStickY, positive: true }),
+            (Action::Left, Key(KeyCode::A)),
+            (Action::Left, Key(KeyCode::Left)),
+            (Action::Left, GamepadButton(Button::DPadLeft)),
+            (Action::Left, GamepadStick { axis: Axis::LeftStickX, positive: false }),
+            (Action::Down, Key(KeyCode::S)),
+            (Action::Down, Key(KeyCode::Down)),
+            (Action::Down, GamepadButton(Button::DPadDown)),
+            (Action::Down, GamepadStick { axis: Axis::LeftStickY, positive: false }),
+            (Action::Right, Key(KeyCode::D)),
+            (Action::Right, Key(KeyCode::Right)),
+            (Action::Right, GamepadButton(Button::DPadRight)),
+            (Action::Right, GamepadStick { axis: Axis::LeftStickX, positive: true }),
+            (Action::Undo, Key(KeyCode::Z)),
+            (Action::Undo, Key(KeyCode::Back)),
+            (Action::Undo, GamepadButton(Button::West)),
+            (Action::Restart, Key(KeyCode::R)),
+            (Action::Restart, GamepadButton(Button::North)),
+            (Action::Pause, Key(KeyCode::Escape)),
+            (Action::Pause, GamepadButton(Button::Start)),
+        ])
+    }
+}
+
+impl Bindings {
+    pub fn of(&self, action: Action) -> impl Iterator<Item = Binding> + '_ {
+        self.0.iter()
+            .filter(move |(x, _)| *x == action)
+            .map(|(_, binding)| *binding)
+    }
+
+    /// Replaces the bindings of the action, which come from the same kind
+    /// of input as `binding` (keys, gamepad buttons or sticks).
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        self.0.retain(|(x, old)| *x != action || !old.is_same_device(&binding));
+        self.0.push((action, binding));
+    }
+}
+
+/// The state of the actions for the current frame. The systems should
+/// read this instead of the raw input.
+#[derive(Resource, Default, Debug)]
+pub struct ActionInput {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionInput {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Releases all the actions, for which `f` returns `false`.
+    pub fn retain(&mut self, f: impl Fn(Action) -> bool) {
+        self.pressed.retain(|x| f(*x));
+        self.just_pressed.retain(|x| f(*x));
+    }
+
+    /// Presses the action for this frame.
+    pub fn press(&mut self, action: Action) {
+        if self.pressed.insert(action) {
+            self.just_pressed.insert(action);
+        }
+    }
+}
+
+/// Sent when the game has acted upon an action. Doesn't get sent for the
+/// actions the game has ignored (like a roll into a wall).
+#[derive(Clone, Copy, Debug)]
+pub struct ActionAccepted(pub Action);
+
+#[derive(SystemLabel, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ActionUpdate;
+
+fn is_binding_pressed(
+    binding: Binding,
+    key_input: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    button_input: &Input<GamepadButton>,
+    axes: &Axis<GamepadAxis>,
+) -> bool {
+    match binding {
+        Binding::Key(key) => key_input.pressed(key),
+        Binding::GamepadButton(button_type) => gamepads.iter()
+            .any(|gamepad| button_input.pressed(GamepadButton::new(gamepad, button_type))),
+        Binding::GamepadStick { axis, positive } => gamepads.iter()
+            .filter_map(|gamepad| axes.get(GamepadAxis::new(gamepad, axis)))
+            .any(|value| match positive {
+                true => value >= STICK_THRESHOLD,
+                false => value <= -STICK_THRESHOLD,
+            }),
+    }
+}
+
+fn update_actions(
+    mut actions: ResMut<ActionInput>,
+    settings: Res<Settings>,
+    key_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    button_input: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+) {
+    let pressed: HashSet<_> = Action::ALL.into_iter()
+        .filter(|action| settings.bindings.of(*action).any(|binding| is_binding_pressed(
+            binding,
+            &key_input,
+            &gamepads,
+            &button_input,
+            &axes,
+        )))
+        .collect();
+
+    actions.just_pressed = pressed.difference(&actions.pressed).copied().collect();
+    actions.pressed = pressed;
+}
+
+/// Turns the keyboard and gamepad input into [ActionInput].
+pub struct ActionPlugin;
+
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ActionInput>()
+            .add_event::<ActionAccepted>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_actions.label(ActionUpdate).after(InputSystem),
+            );
+    }
+}
@@ -1,7 +1,13 @@
+use crate::replay::Replay;
+
 pub struct LaunchParams<'a> {
     pub logging: bool,
     pub inspector: bool,
     pub level_file: Option<&'a str>,
+    /// The replay to play back on `level_file`
+    pub replay: Option<Replay>,
+    /// The directory to record the level attempts into. Ignored on the web.
+    pub replay_dir: Option<&'a str>,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -11,6 +17,8 @@ impl Default for LaunchParams<'static> {
             logging: cfg!(debug_assertions),
             inspector: cfg!(debug_assertions),
             level_file: None,
+            replay: None,
+            replay_dir: None,
         }
     }
 }
@@ -0,0 +1,321 @@
+//! Levels played without a window or a GPU. The app runs [MoveablePlugin]
+//! and [TilePlugin] on `MinimalPlugins`, with a clock that only moves when
+//! asked to.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use bevy::asset::{AssetPlugin, LoadState};
+use bevy::ecs::system::System;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy_ecs_tilemap::prelude::*;
+use bevy_tiled::{tileset_indexing, TiledMap, TiledPlugin};
+use cube_rot::MoveDirection;
+use iyes_loopless::prelude::*;
+use puzzle_rules::{Layout, Pos, Status};
+
+use crate::level::{get_level_map, register_graphics_builder, spawn_level, BaseLevelAssets};
+use crate::moveable::{MoveablePlugin, MoveableQuery, MoveableTilemapTag, Side};
+use crate::player::{spawn_player, PlayerWinnerTag};
+use crate::settings::Settings;
+use crate::states::PauseState;
+use crate::tile::{
+    ButtonCondition,
+    LevelRules,
+    LogicState,
+    LogicTileBundle,
+    LogicTilemapTag,
+    TilePlugin,
+};
+
+/// The length of a frame for [HeadlessLevel::settle].
+pub const FRAME: Duration = Duration::from_nanos(16_666_667);
+
+/// How long [HeadlessLevel::settle] waits for the player to come to rest.
+pub const SETTLE_LIMIT: Duration = Duration::from_secs(20);
+
+/// How long [HeadlessLevel::from_tmx] waits for the map to load.
+const LOAD_LIMIT: Duration = Duration::from_secs(20);
+
+/// How a headless run of a level has ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayOutcome {
+    /// The player has reached an active exit
+    Won,
+    /// The player has been destroyed
+    Died,
+    /// The player got stuck on the conveyors
+    Stuck,
+    /// The moves have run out before the level was over
+    OutOfMoves,
+}
+
+impl PlayOutcome {
+    /// The process exit code for the outcome. `1` is left for the errors
+    /// and `2` for the command line errors.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            PlayOutcome::Won => 0,
+            PlayOutcome::Died => 3,
+            PlayOutcome::Stuck => 4,
+            PlayOutcome::OutOfMoves => 5,
+        }
+    }
+}
+
+fn tile_pos(pos: Pos) -> TilePos {
+    TilePos { x: pos.x, y: pos.y }
+}
+
+/// Spawns the logic tilemap and the triggers the same way `spawn_level`
+/// would, minus the graphics.
+fn spawn_layout(world: &mut World, layout: &Layout) {
+    let size = TilemapSize {
+        x: layout.tiles().iter().map(|tile| tile.pos.x + 1).max().unwrap_or_default(),
+        y: layout.tiles().iter().map(|tile| tile.pos.y + 1).max().unwrap_or_default(),
+    };
+
+    let tilemap = world.spawn_empty().id();
+    let mut storage = TileStorage::empty(size);
+    for tile in layout.tiles() {
+        let pos = tile_pos(tile.pos);
+        let entity = world
+            .spawn((
+                LogicTileBundle {
+                    ty: tile.kind,
+                    state: LogicState(false),
+                },
+                pos,
+                TileFlip { x: tile.flip.x, y: tile.flip.y, d: tile.flip.d },
+                TilemapId(tilemap),
+            ))
+            .id();
+        storage.set(&pos, entity);
+    }
+    world.entity_mut(tilemap).insert((
+        storage,
+        size,
+        LogicTilemapTag,
+        MoveableTilemapTag,
+    ));
+
+    for &(idx, cond) in layout.side_triggers() {
+        let pos = tile_pos(layout.tiles()[idx].pos);
+        world.spawn((cond, pos));
+    }
+    for &(idx, button_id) in layout.button_triggers() {
+        let pos = tile_pos(layout.tiles()[idx].pos);
+        world.spawn((ButtonCondition(button_id), pos));
+    }
+}
+
+/// Runs a system once, outside of the schedule.
+fn run_once<Out, Params>(world: &mut World, system: impl IntoSystem<(), Out, Params>) -> Out {
+    let mut system = IntoSystem::into_system(system);
+    system.initialize(world);
+    let out = system.run((), world);
+    system.apply_buffers(world);
+
+    out
+}
+
+/// Updates the app until the map is loaded.
+fn wait_for_map(app: &mut App, map: &Handle<TiledMap>) -> anyhow::Result<()> {
+    let started = Instant::now();
+
+    loop {
+        app.update();
+
+        match app.world.resource::<AssetServer>().get_load_state(map) {
+            LoadState::Loaded => return Ok(()),
+            LoadState::Failed => bail!("The map has failed to load"),
+            _ if started.elapsed() > LOAD_LIMIT => bail!("The map hasn't loaded in {LOAD_LIMIT:?}"),
+            _ => std::thread::sleep(Duration::from_millis(1)),
+        }
+    }
+}
+
+/// An app with the gameplay plugins. The clock is still the real one.
+fn gameplay_app(assets: AssetPlugin) -> App {
+    let mut app = App::new();
+
+    app
+        .add_plugins(MinimalPlugins)
+        // The tile animations are assets
+        .add_plugin(assets)
+        .init_resource::<Settings>()
+        .add_loopless_state(PauseState::Running)
+        .add_plugin(MoveablePlugin)
+        .add_plugin(TilePlugin);
+
+    app
+}
+
+/// A level running in a minimal app.
+pub struct HeadlessLevel {
+    app: App,
+    player: Entity,
+    now: Instant,
+}
+
+impl HeadlessLevel {
+    pub fn new(layout: &Layout) -> anyhow::Result<Self> {
+        let mut app = gameplay_app(AssetPlugin::default());
+        spawn_layout(&mut app.world, layout);
+        let (player, _) = run_once(&mut app.world, spawn_player)?;
+
+        Ok(Self::start(app, player))
+    }
+
+    /// Loads a map file through the asset loader and spawns it with
+    /// `spawn_level`, just like the game does. The tilesets are found
+    /// relative to the map, so the map has to be in a folder of the
+    /// assets, like the shipped ones in `assets/maps`.
+    pub fn from_tmx(path: &Path) -> anyhow::Result<Self> {
+        let path = std::fs::canonicalize(path)
+            .with_context(|| format!("Resolving {path:?}"))?;
+        let root = path.parent().and_then(Path::parent)
+            .with_context(|| format!("{path:?} isn't in a folder of the assets"))?;
+        let map_path = path.strip_prefix(root)?;
+
+        let mut app = gameplay_app(AssetPlugin {
+            asset_folder: root.to_string_lossy().into_owned(),
+            watch_for_changes: false,
+        });
+        // The tileset images are only needed as handles
+        app
+            .add_asset::<Image>()
+            .add_plugin(TiledPlugin);
+        register_graphics_builder(&mut app);
+
+        let map = app.world.resource::<AssetServer>().load(map_path);
+        wait_for_map(&mut app, &map)
+            .with_context(|| format!("Loading {path:?}"))?;
+        app.insert_resource(BaseLevelAssets { map });
+        run_once(&mut app.world, get_level_map.pipe(tileset_indexing).pipe(spawn_level));
+
+        let (player, _) = run_once(&mut app.world, spawn_player)
+            .with_context(|| format!("Spawning {path:?}"))?;
+
+        Ok(Self::start(app, player))
+    }
+
+    /// Sets up the clock and runs the first update.
+    fn start(mut app: App, player: Entity) -> Self {
+        let now = Instant::now();
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(now));
+
+        let mut level = HeadlessLevel { app, player, now };
+        // The first update only starts the clock
+        level.step(Duration::ZERO);
+
+        level
+    }
+
+    pub fn app(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    /// Advances the clock by `dt` and runs the schedule once.
+    pub fn step(&mut self, dt: Duration) {
+        self.now += dt;
+        self.app.insert_resource(TimeUpdateStrategy::ManualInstant(self.now));
+        self.app.update();
+    }
+
+    /// Hands a roll to the rules, the same way the controls do. Returns
+    /// `false` if the roll has been denied.
+    pub fn roll(&mut self, dir: MoveDirection) -> bool {
+        if !self.is_player_alive() || self.is_player_moving() { return false; }
+
+        self.app.world.resource_mut::<LevelRules>().roll(dir)
+    }
+
+    /// Steps the schedule frame by frame until the player is at rest and
+    /// the rules have played back all the outcomes. Returns `false` if that
+    /// hasn't happened in 20 seconds.
+    pub fn settle(&mut self) -> bool {
+        let mut elapsed = Duration::ZERO;
+
+        loop {
+            if elapsed >= SETTLE_LIMIT { return false; }
+
+            self.step(FRAME);
+            elapsed += FRAME;
+
+            if self.is_settled() { return true; }
+        }
+    }
+
+    /// Tells whether the player is at rest and the rules have played back
+    /// all the outcomes.
+    pub fn is_settled(&mut self) -> bool {
+        !self.is_player_moving() && self.app.world.resource::<LevelRules>().is_settled()
+    }
+
+    /// The position of the player. `None` once the player is destroyed
+    /// or has reached the exit.
+    pub fn player_pos(&mut self) -> Option<Pos> {
+        let mut query = self.app.world.query::<MoveableQuery>();
+        let player = query.get_mut(&mut self.app.world, self.player).ok()?;
+        let TilePos { x, y } = player.snapshot().tile_pos();
+
+        Some(Pos::new(x, y))
+    }
+
+    pub fn player_side(&self) -> Option<Side> {
+        self.app.world.get::<Side>(self.player).copied()
+    }
+
+    fn is_player_moving(&mut self) -> bool {
+        let mut query = self.app.world.query::<MoveableQuery>();
+        matches!(query.get_mut(&mut self.app.world, self.player), Ok(player) if player.is_moving())
+    }
+
+    pub fn is_player_alive(&self) -> bool {
+        self.app.world.get_entity(self.player).is_some()
+    }
+
+    pub fn has_won(&self) -> bool {
+        self.app.world.get::<PlayerWinnerTag>(self.player).is_some()
+    }
+
+    /// The state of the logic tile at the position.
+    pub fn tile_state(&mut self, pos: Pos) -> Option<bool> {
+        let mut query = self.app.world.query::<(&TilePos, &LogicState)>();
+
+        query.iter(&self.app.world)
+            .find(|(at, _)| **at == tile_pos(pos))
+            .map(|(_, state)| state.0)
+    }
+
+    /// The outcome, once the level is over.
+    pub fn outcome(&self) -> Option<PlayOutcome> {
+        if self.has_won() { return Some(PlayOutcome::Won); }
+        if !self.is_player_alive() { return Some(PlayOutcome::Died); }
+
+        match self.app.world.resource::<LevelRules>().state().status() {
+            Status::Looping => Some(PlayOutcome::Stuck),
+            _ => None,
+        }
+    }
+}
+
+/// Loads the map file the way the game does and plays `moves` on it with
+/// the gameplay systems, until the level is over.
+pub fn play_headless(path: &Path, moves: &[MoveDirection]) -> anyhow::Result<PlayOutcome> {
+    let mut level = HeadlessLevel::from_tmx(path)?;
+
+    for &dir in moves {
+        if let Some(outcome) = level.outcome() { return Ok(outcome); }
+
+        // The denied rolls are skipped, just like in the game
+        if level.roll(dir) && !level.settle() {
+            bail!("The level hasn't settled after the roll {dir:?}");
+        }
+    }
+
+    Ok(level.outcome().unwrap_or(PlayOutcome::OutOfMoves))
+}
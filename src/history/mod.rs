@@ -0,0 +1,47 @@
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::player::{PlayerInputStage, PlayerSystem};
+use crate::states::{GameState, PauseState};
+use crate::tile::LevelRules;
+
+pub use resources::*;
+pub use systems::*;
+
+#[derive(SystemLabel, Debug, PartialEq, Eq, Hash, Clone)]
+enum HistorySystem {
+    Record,
+}
+
+/// Keeps track of the level's state after each roll and lets the player
+/// rewind their moves.
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<MoveHistory>()
+            .add_enter_system(GameState::InGame, reset_history)
+            .add_system_to_stage(
+                PlayerInputStage,
+                record_history
+                    .run_in_state(GameState::InGame)
+                    .run_in_state(PauseState::Running)
+                    .run_if_resource_exists::<LevelRules>()
+                    .label(HistorySystem::Record)
+                    .before(PlayerSystem::Controls),
+            )
+            .add_system_to_stage(
+                PlayerInputStage,
+                rewind_history
+                    .run_in_state(GameState::InGame)
+                    .run_in_state(PauseState::Running)
+                    .run_if_resource_exists::<LevelRules>()
+                    .after(HistorySystem::Record)
+                    .before(PlayerSystem::Controls),
+            );
+    }
+}
@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+use puzzle_rules::LevelState;
+
+use crate::moveable::MoveableSnapshot;
+use crate::tile::LogicState;
+
+/// The state of the level once the player has come to rest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub player: MoveableSnapshot,
+    pub tiles: Vec<(Entity, LogicState)>,
+    pub rules: LevelState,
+}
+
+/// The states the level has been in, from the oldest to the latest one.
+/// The first entry is the state the level has started in.
+#[derive(Resource, Default, Debug)]
+pub struct MoveHistory {
+    entries: Vec<HistoryEntry>,
+}
+
+impl MoveHistory {
+    pub fn latest(&self) -> Option<&HistoryEntry> {
+        self.entries.last()
+    }
+
+    /// Records a new state of the level. A completed roll always changes
+    /// the upper side of the player, so if the player is still where the
+    /// latest entry has them, the entry gets updated instead. This way the
+    /// tiles that change a bit later (like button triggers) end up in the
+    /// same entry.
+    pub fn record(&mut self, entry: HistoryEntry) {
+        match self.entries.last_mut() {
+            Some(latest) if latest.player == entry.player => *latest = entry,
+            _ => self.entries.push(entry),
+        }
+    }
+
+    /// Forgets the latest entry. The starting state is never forgotten.
+    pub fn pop(&mut self) -> Option<HistoryEntry> {
+        if self.entries.len() > 1 {
+            self.entries.pop()
+        } else {
+            None
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use std::time::Duration;
+
+use crate::action::{Action, ActionAccepted, ActionInput};
+use crate::level::tile_pos_to_world_pos;
+use crate::moveable::{MoveableQuery, MoveableState, MoveableTilemapTag};
+use crate::player::{spawn_player_entity, GeneratedPlayerAssets, PlayerTag, PlayerWinnerTag};
+use crate::tile::{LevelRules, LogicState};
+use super::{HistoryEntry, MoveHistory};
+
+/// How long the undo has to be held before the rewinding repeats
+const REWIND_DELAY: f32 = 0.4f32;
+/// How often the rewinding repeats while the undo is held
+const REWIND_INTERVAL: f32 = 0.12f32;
+
+pub fn reset_history(mut commands: Commands) {
+    commands.insert_resource(MoveHistory::default());
+}
+
+/// Records the state of the level each time the player comes to rest.
+pub fn record_history(
+    mut history: ResMut<MoveHistory>,
+    rules: Res<LevelRules>,
+    mut player_q: Query<MoveableQuery, With<PlayerTag>>,
+    moved_q: Query<(), (With<PlayerTag>, Changed<MoveableState>)>,
+    changed_tiles_q: Query<(), Changed<LogicState>>,
+    tile_q: Query<(Entity, &LogicState)>,
+) {
+    let player = match player_q.get_single_mut() {
+        Ok(x) => x,
+        Err(_) => return,
+    };
+
+    if player.is_moving() || !rules.is_settled() { return; }
+    if !history.is_empty() && moved_q.is_empty() && changed_tiles_q.is_empty() {
+        return;
+    }
+
+    history.record(HistoryEntry {
+        player: player.snapshot(),
+        tiles: tile_q.iter().map(|(e, state)| (e, *state)).collect(),
+        rules: rules.state().clone(),
+    });
+}
+
+#[derive(Default)]
+pub struct RewindRepeat(Option<Timer>);
+
+fn check_rewind(
+    repeat: &mut RewindRepeat,
+    actions: &ActionInput,
+    delta: Duration,
+) -> bool {
+    if actions.just_pressed(Action::Undo) {
+        repeat.0 = Some(Timer::from_seconds(REWIND_DELAY, TimerMode::Once));
+        return true;
+    }
+
+    if !actions.pressed(Action::Undo) {
+        repeat.0 = None;
+        return false;
+    }
+
+    match repeat.0.as_mut() {
+        Some(timer) => {
+            timer.tick(delta);
+            if !timer.finished() { return false; }
+
+            *timer = Timer::from_seconds(REWIND_INTERVAL, TimerMode::Once);
+            true
+        },
+        None => false,
+    }
+}
+
+/// The system for rewinding the player's moves. Pressing the undo button
+/// puts the level back into the state it was in before the latest roll.
+/// Holding it keeps rewinding.
+///
+/// If the player is in the middle of a roll (or has been destroyed during
+/// it), the roll gets cancelled instead.
+#[allow(clippy::too_many_arguments)]
+pub fn rewind_history(
+    mut commands: Commands,
+    mut repeat: Local<RewindRepeat>,
+    time: Res<Time>,
+    actions: Res<ActionInput>,
+    mut accepted: EventWriter<ActionAccepted>,
+    mut history: ResMut<MoveHistory>,
+    mut rules: ResMut<LevelRules>,
+    mut player_q: Query<MoveableQuery, With<PlayerTag>>,
+    winner_q: Query<(), With<PlayerWinnerTag>>,
+    mut tile_q: Query<&mut LogicState>,
+    map_q: Query<(&Transform, &TilemapGridSize), With<MoveableTilemapTag>>,
+    generated_assets: Res<GeneratedPlayerAssets>,
+) {
+    if !check_rewind(&mut repeat, &actions, time.delta()) { return; }
+
+    // The level is already beaten
+    if !winner_q.is_empty() { return; }
+
+    let mut player = player_q.get_single_mut().ok();
+    if matches!(&player, Some(player) if !player.is_moving()) {
+        history.pop();
+    }
+
+    let entry = match history.latest() {
+        Some(x) => x.clone(),
+        None => return,
+    };
+
+    match player.as_mut() {
+        Some(player) => player.restore(entry.player),
+        None => {
+            let (map_tf, map_grid) = match map_q.get_single() {
+                Ok(x) => x,
+                Err(e) => {
+                    error!("Failed to query the level map: {}", e);
+                    return;
+                }
+            };
+
+            info!("Bringing the player back");
+            spawn_player_entity(
+                &mut commands,
+                entry.player.bundle(),
+                tile_pos_to_world_pos(entry.player.tile_pos(), map_tf, map_grid),
+                &generated_assets,
+            );
+        },
+    }
+
+    accepted.send(ActionAccepted(Action::Undo));
+    rules.restore(entry.rules);
+
+    for (e, saved) in entry.tiles {
+        if let Ok(mut state) = tile_q.get_mut(e) {
+            if *state != saved { *state = saved; }
+        }
+    }
+}
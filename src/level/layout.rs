@@ -0,0 +1,119 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use bevy_tiled::{tiled, TileBounds, TileExt, TileLayerExt};
+use puzzle_rules::{Flip, Layout, LevelState, Pos};
+use tiled::{Layer, LayerTile, LayerType, Map, TileLayer};
+
+use crate::tile::{ButtonCondition, LogicTileBundle, SideCondition, TriggerTile};
+
+/// A tile that matters for the rules of the game.
+#[derive(Clone)]
+pub enum RulesTile {
+    Logic(LogicTileBundle, Flip),
+    SideTrigger(SideCondition),
+    ButtonTrigger(ButtonCondition),
+}
+
+/// Reads a tile of a map the same way `spawn_level` would. Returns `None`
+/// for tiles which don't belong to the logic or trigger tilesets.
+pub fn read_rules_tile(tile: &LayerTile) -> anyhow::Result<Option<RulesTile>> {
+    let data = match tile.get_tile() {
+        Some(x) => x,
+        None => return Ok(None),
+    };
+    let flip = Flip { x: tile.flip_h, y: tile.flip_v, d: tile.flip_d };
+
+    let res = match tile.get_tileset().name.as_str() {
+        "logic_tiles" => RulesTile::Logic(data.properties()?, flip),
+        "activator_tiles" => match data.properties()? {
+            TriggerTile::TriggerTileBundle(bundle) => RulesTile::SideTrigger(bundle.active),
+            TriggerTile::ButtonTriggerTileBundle(bundle) => RulesTile::ButtonTrigger(bundle.button),
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(res))
+}
+
+/// Loads a map file straight from the disk.
+pub fn load_map(path: &Path) -> anyhow::Result<Map> {
+    tiled::Loader::new().load_tmx_map(path)
+        .with_context(|| format!("Loading {path:?}"))
+}
+
+/// Loads a map file straight from the disk and converts it into the
+/// layout for the rules model.
+pub fn load_layout(path: &Path) -> anyhow::Result<Layout> {
+    layout_from_map(&load_map(path)?)
+}
+
+/// Loads a map file and puts the player on its start.
+pub fn load_level_state(path: &Path) -> anyhow::Result<LevelState> {
+    Ok(LevelState::new(Arc::new(load_layout(path)?))?)
+}
+
+/// Collects the logic and trigger tiles of a map into a layout for the
+/// rules model. The tiles get the same positions `spawn_level` gives them.
+/// Layers from other tilesets are skipped.
+pub fn layout_from_map(map: &Map) -> anyhow::Result<Layout> {
+    let bounds = TileBounds::of_map(map);
+    let mut tiles = Vec::new();
+
+    for layer in map.layers() {
+        collect_layer(layer, &bounds, &mut tiles)
+            .with_context(|| format!("While parsing layer {:?}", layer.name))?;
+    }
+
+    // Triggers can only be attached once all the logic tiles are known
+    let mut layout = Layout::new();
+    for (pos, tile) in &tiles {
+        if let RulesTile::Logic(bundle, flip) = tile {
+            layout.add_tile(*pos, bundle.ty, *flip);
+        }
+    }
+    for (pos, tile) in tiles {
+        match tile {
+            RulesTile::SideTrigger(cond) => { layout.add_side_trigger(pos, cond); },
+            RulesTile::ButtonTrigger(cond) => { layout.add_button_trigger(pos, cond.0); },
+            RulesTile::Logic(..) => (),
+        }
+    }
+
+    Ok(layout)
+}
+
+fn collect_layer(
+    layer: Layer,
+    bounds: &TileBounds,
+    tiles: &mut Vec<(Pos, RulesTile)>,
+) -> anyhow::Result<()> {
+    match layer.layer_type() {
+        LayerType::GroupLayer(group) => group.layers()
+            .try_for_each(|layer| collect_layer(layer, bounds, tiles)
+                .with_context(|| format!("While parsing layer {:?}", layer.name))
+            ),
+        LayerType::TileLayer(layer) => collect_tiles(&layer, bounds, tiles),
+        _ => Ok(()),
+    }
+}
+
+fn collect_tiles(
+    layer: &TileLayer,
+    bounds: &TileBounds,
+    tiles: &mut Vec<(Pos, RulesTile)>,
+) -> anyhow::Result<()> {
+    for ((x, y), tile) in layer.tiles() {
+        let read = read_rules_tile(&tile)
+            .with_context(|| format!("Error while reading tile ({x}, {y})"))?;
+        let pos = bounds.tile_pos((x, y))
+            .with_context(|| format!("The tile ({x}, {y}) is out of the map bounds"))?;
+
+        if let Some(read) = read {
+            tiles.push((Pos::new(pos.x, pos.y), read));
+        }
+    }
+
+    Ok(())
+}
@@ -1,13 +1,25 @@
 mod resources;
+mod layout;
+mod objects;
+mod property_types;
+mod validate;
 
 use bevy_ecs_tilemap::prelude::*;
+use bevy_ecs_tilemap_cpu_anim::CPUTileAnimation;
+use bevy::asset::{AssetPath, HandleId};
+use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
 
 pub use resources::*;
+pub use layout::*;
+pub use objects::*;
+pub use property_types::*;
+pub use validate::*;
 
 use crate::tile::*;
 use bevy_tiled::*;
-use crate::moveable::MoveableTilemapTag;
 
 #[derive(Default)]
 pub struct LevelPlugin;
@@ -18,9 +30,17 @@ impl Plugin for LevelPlugin {
             .add_plugin(TiledPlugin)
             .add_plugin(TilemapPlugin)
             .add_plugin(TilePlugin);
+
+        register_graphics_builder(app);
     }
 }
 
+/// Registers the builder of the graphics tiles, which are the only
+/// ones this module spawns by itself.
+pub fn register_graphics_builder(app: &mut App) {
+    app.register_tileset_builder_with("graphics_tiles", || Box::new(GraphicsTileBuilder::default()));
+}
+
 pub fn tile_pos_to_world_pos(
     tile_pos: TilePos,
     map_transform: &Transform,
@@ -40,164 +60,173 @@ pub fn get_level_map(
     base_level_assets.map.clone()
 }
 
-// #[derive(Default, Clone, Copy, Debug, Deserialize)]
-// struct TileAnimationFrame {
-//     id: u32,
-//     dur: u64,
-// }
-
-// #[derive(Default, Debug, Deserialize)]
-// struct TileAnimation(Vec<TileAnimationFrame>);
-
-// impl TileAnimation {
-//     fn decode(self, indexing: &TilesetIndexing) -> CPUTileAnimation {
-//         CPUTileAnimation::new(
-//             self.0.into_iter()
-//                 .map(|frame| Frame {
-//                     texture_id: indexing.dispatch(frame.id),
-//                     duration: Duration::from_millis(frame.dur),
-//                 })
-//         )
-//     }
-// }
-
-// impl GraphicsAnimating<TileAnimation> {
-//     fn decode(
-//         self,
-//         tileset: usize,
-//         tile: u32,
-//         assets: &mut Assets<CPUTileAnimation>,
-//         map_path: Option<&Path>,
-//         indexing: &TilesetIndexing,
-//     ) -> GraphicsAnimating {
-//         let mut acquire_asset = |anim, tag: &'static str| {
-//             let asset = TileAnimation::decode(anim, indexing);
-//             debug!("anim{tileset:}_{tile:}_{tag:}\n{asset:?}");
-
-//             match map_path {
-//                 Some(path) => assets.set(AssetPath::new_ref(
-//                     path, Some(&format!("anim{tileset:}_{tile:}_{tag:}"))
-//                 ), asset),
-//                 None => assets.add(asset),
-//             }
-//         };
-
-//         GraphicsAnimating {
-//             on_transit: acquire_asset(self.on_transit, "on_transition"),
-//             off_transit: acquire_asset(self.off_transit, "off_transition"),
-//             on_anim: acquire_asset(self.on_anim, "off_anim"),
-//             off_anim: acquire_asset(self.off_anim, "on_anim"),
-//         }
-//     }
-// }
-
-// struct GraphicsTileBuilder<'a> {
-//     map_path: Option<&'a Path>,
-//     anims: &'a mut Assets<CPUTileAnimation>,
-//     deserialized_props: HashMap<(usize, u32), GraphicsTileBundle>,
-// }
-
-// impl<'a> TileBuilder for GraphicsTileBuilder<'a> {
-//     fn process_tileset(
-//         &mut self,
-//         set_id: usize,
-//         tileset: &tiled::Tileset,
-//         indexing: &TilesetIndexing,
-//     ) -> anyhow::Result<()> {
-//         self.deserialized_props.reserve(tileset.tilecount as usize);
-
-//         for (id, tile) in tileset.tiles() {
-//             let props: GraphicsTileBundle<TileAnimation> = tile.properties()?;
-//             self.deserialized_props.insert(
-//                 (set_id, id),
-//                 GraphicsTileBundle {
-//                     animating: props.animating.decode(
-//                         set_id,
-//                         id,
-//                         self.anims,
-//                         self.map_path,
-//                         indexing
-//                     ),
-//                 }
-//             );
-//         }
-
-//         Ok(())
-//     }
-
-//     fn build(
-//         &mut self,
-//         set_id: usize,
-//         id: u32,
-//         cmds: &mut bevy::ecs::system::EntityCommands,
-//     ) -> anyhow::Result<()> {
-//         cmds.insert(self.deserialized_props[&(set_id, id)].clone());
-
-//         Ok(())
-//     }
-
-//     fn finish_layer(
-//         &mut self,
-//         _set_id: usize,
-//         cmds: &mut bevy::ecs::system::EntityCommands,
-//     ) -> anyhow::Result<()> {
-//         cmds.insert(GraphicsTilemapTag);
-
-//         Ok(())
-//     }
-// }
+/// A frame of a tile animation. In the tileset it's a `TileAnimationFrame`
+/// class and the frames of an animation are the members `0`, `1` and so
+/// on of a `TileAnimation` class. Tiled leaves out the members, which are
+/// still `0`.
+#[derive(Clone, Copy, Default, Debug, Deserialize)]
+#[serde(default)]
+struct TileAnimationFrame {
+    id: u32,
+    dur: u32,
+}
+
+/// The `animating` property of the graphics tiles, a `TileAnimations`
+/// class. Any of the animations can be left out.
+#[derive(Clone, Default, Debug, Deserialize)]
+#[serde(default)]
+struct TileAnimations {
+    on_transition: Option<Vec<TileAnimationFrame>>,
+    off_transition: Option<Vec<TileAnimationFrame>>,
+    on_anim: Option<Vec<TileAnimationFrame>>,
+    off_anim: Option<Vec<TileAnimationFrame>>,
+}
+
+impl TileAnimations {
+    /// Turns the animations into assets. The missing transitions fall
+    /// back onto the animations they lead to, so the tile still ends up
+    /// playing the right animation.
+    fn decode(
+        self,
+        mut acquire_asset: impl FnMut(&[TileAnimationFrame], &str) -> Handle<CPUTileAnimation>,
+    ) -> GraphicsAnimating {
+        let mut decode = |anim: Option<Vec<TileAnimationFrame>>, tag: &str| anim
+            .map(|anim| acquire_asset(&anim, tag))
+            .unwrap_or_default();
+
+        let on_anim = decode(self.on_anim, "on_anim");
+        let off_anim = decode(self.off_anim, "off_anim");
+        let on_transit = match self.on_transition {
+            Some(anim) => decode(Some(anim), "on_transition"),
+            None => on_anim.clone(),
+        };
+        let off_transit = match self.off_transition {
+            Some(anim) => decode(Some(anim), "off_transition"),
+            None => off_anim.clone(),
+        };
+
+        GraphicsAnimating {
+            on_transit,
+            off_transit,
+            on_anim,
+            off_anim,
+        }
+    }
+}
+
+/// The properties of a graphics tile. The tiles without animations don't
+/// need the `animating` property.
+#[derive(Deserialize)]
+#[serde(rename = "GraphicsTileBundle")]
+struct GraphicsTileProps {
+    #[serde(default)]
+    animating: Option<TileAnimations>,
+}
+
+/// Builds the graphics tiles. The animations of the tiles are labeled
+/// assets of their tileset, so respawning the level reuses them. The
+/// builder has no access to the assets, so it queues them up and adds
+/// them with a command once a layer is finished.
+#[derive(Default)]
+struct GraphicsTileBuilder {
+    deserialized_props: HashMap<(usize, u32), GraphicsTileBundle>,
+    pending_anims: Vec<(HandleId, CPUTileAnimation)>,
+}
+
+impl TileBuilder for GraphicsTileBuilder {
+    fn process_tileset(
+        &mut self,
+        set_id: usize,
+        tileset: &TilesetData,
+    ) -> anyhow::Result<()> {
+        let indexing = &tileset.indexing;
+        let path = tileset.tileset.source.as_path();
+        self.deserialized_props.reserve(tileset.tileset.tilecount as usize);
+
+        for (&id, props) in tileset.properties::<GraphicsTileProps>()?.iter() {
+            let pending_anims = &mut self.pending_anims;
+            let animating = props.animating.clone().unwrap_or_default().decode(|anim, tag| {
+                let frames: Vec<_> = anim.iter()
+                    .map(|frame| tiled::Frame { tile_id: frame.id, duration: frame.dur })
+                    .collect();
+                let handle_id = HandleId::from(
+                    AssetPath::new_ref(path, Some(&format!("anim{id}_{tag}")))
+                );
+                pending_anims.push((handle_id, indexing.cpu_tile_anim(&frames)));
+
+                Handle::weak(handle_id)
+            });
+
+            self.deserialized_props.insert((set_id, id), GraphicsTileBundle { animating });
+        }
+
+        Ok(())
+    }
+
+    fn build(
+        &mut self,
+        set_id: usize,
+        id: u32,
+        cmds: &mut EntityCommands,
+    ) -> anyhow::Result<()> {
+        match self.deserialized_props.get(&(set_id, id)) {
+            Some(bundle) => { cmds.insert(bundle.clone()); },
+            None => anyhow::bail!("Tile {} didn't have any deserialized properties", id),
+        }
+
+        Ok(())
+    }
+
+    fn finish_layer(
+        &mut self,
+        _set_id: usize,
+        cmds: &mut EntityCommands,
+    ) -> anyhow::Result<()> {
+        cmds.insert(GraphicsTilemapTag);
+
+        let pending_anims = std::mem::take(&mut self.pending_anims);
+        cmds.commands().add(move |world: &mut World| {
+            let mut anims = world.resource_mut::<Assets<CPUTileAnimation>>();
+            for (handle_id, anim) in pending_anims {
+                anims.set_untracked(handle_id, anim);
+            }
+        });
+
+        Ok(())
+    }
+}
 
 // NOTE I don't think I can do anything here to satisfy clippy.
 // Maybe some further investigation will prove me wrong.
 #[allow(clippy::too_many_arguments)]
 pub fn spawn_level(
     In(tilemap_texture_data): In<Vec<(TilesetIndexing, TilemapTexture)>>,
-    // asset_server: Res<AssetServer>,
     mut commands: Commands,
     base_level_assets: Res<BaseLevelAssets>,
     maps: Res<Assets<TiledMap>>,
-    // mut animations: ResMut<Assets<CPUTileAnimation>>,
+    images: Res<Assets<Image>>,
+    mut animations: ResMut<Assets<CPUTileAnimation>>,
+    tile_builders: Res<TileBuilderRegistry>,
 ) {
-    // let map_asset_path = asset_server.get_handle_path(base_level_assets.map.clone());
-
-    let mut logic_tile_builder = BasicDeserBuilder::<LogicTileBundle, _>::new(|cmds| {
-        cmds
-            .insert((
-                LogicTilemapTag,
-                MoveableTilemapTag,
-                Visibility { is_visible: false },
-            ));
-    });
-    let mut trigger_tile_builder = BasicDeserBuilder::<TriggerTileBundle, _>::new(|cmds| {
-        cmds
-            .insert((
-                TriggerTilemapTag,
-                Visibility { is_visible: false },
-            ));
-    });
-    // let mut graphics_tile_builder = GraphicsTileBuilder {
-    //     map_path: map_asset_path.as_ref().map(|x| x.path()),
-    //     anims: &mut animations,
-    //     deserialized_props: HashMap::new(),
-    // };
+    let map_asset = maps.get(&base_level_assets.map).unwrap();
+    let map = &map_asset.map;
+    let layer_images = LayerImages::from_map(map_asset, &images);
+    let native_animations = TilesetAnimations::from_map(
+        map,
+        &tilemap_texture_data,
+        &mut animations,
+    );
 
+    let mut object_builder = LevelObjectBuilder;
     let res = MapParser::new(
         &mut commands,
-        SimpleCallbackSelector {
-            pool: [
-                &mut logic_tile_builder,
-                &mut trigger_tile_builder,
-                //&mut graphics_tile_builder,
-            ],
-            picker: |name| match name {
-                "logic_tiles" => 0,
-                "activator_tiles" => 1,
-                _ => 2,
-            },
-        },
+        tile_builders.selector(None),
         &tilemap_texture_data,
+        &native_animations,
+        &layer_images,
     )
-    .parse_map(&maps.get(&base_level_assets.map).unwrap().map);
+    .with_object_builder(&mut object_builder)
+    .parse_map(map_asset);
 
     if let Err(e) = res {
         error!("Error parsing map: {e}");
@@ -0,0 +1,72 @@
+use std::path::Path;
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_tiled::{tiled, MapObject, ObjectBuilder, TilePropertyDes, TilePropertyDeserError};
+use serde::Deserialize;
+
+/// A hint for the player, shown while they are on the tile of the object.
+#[derive(Clone, Debug, Component, Deserialize)]
+pub struct Hint {
+    pub text: String,
+}
+
+/// The objects the levels can have. The class of the object picks the
+/// variant.
+#[derive(Clone, Debug, Deserialize)]
+pub enum LevelObject {
+    Hint(Hint),
+}
+
+/// Marks the object layers of the level.
+#[derive(Clone, Copy, Default, Debug, Component)]
+pub struct ObjectLayerTag;
+
+/// Reads the object. The objects without a class are notes for the
+/// designers and are left out.
+pub fn read_level_object(
+    object: &tiled::ObjectData,
+    base_dir: Option<&Path>,
+) -> Result<Option<LevelObject>, TilePropertyDeserError> {
+    if object.user_type.is_empty() {
+        return Ok(None);
+    }
+
+    LevelObject::deserialize(TilePropertyDes::from_object(object).with_base_dir(base_dir))
+        .map(Some)
+}
+
+/// Builds the objects of the level. Each of them gets the tile its top
+/// left corner is in.
+#[derive(Default)]
+pub struct LevelObjectBuilder;
+
+impl ObjectBuilder for LevelObjectBuilder {
+    fn build(
+        &mut self,
+        object: &MapObject,
+        cmds: &mut EntityCommands,
+    ) -> anyhow::Result<()> {
+        let level_object = match read_level_object(object.data, object.base_dir)? {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        let pos = object.tile_pos()
+            .ok_or_else(|| anyhow::anyhow!("The object is outside of the map"))?;
+
+        match level_object {
+            LevelObject::Hint(hint) => { cmds.insert((hint, pos)); },
+        }
+
+        Ok(())
+    }
+
+    fn finish_layer(
+        &mut self,
+        cmds: &mut EntityCommands,
+    ) -> anyhow::Result<()> {
+        cmds.insert(ObjectLayerTag);
+
+        Ok(())
+    }
+}
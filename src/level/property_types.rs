@@ -0,0 +1,351 @@
+//! Generates the custom property types of the Tiled project from the
+//! reflection data of the tile types, so that the editor offers exactly
+//! the classes, members and enum values the game can deserialize.
+
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use bevy::reflect::{TypeInfo, Typed, VariantInfo};
+use bevy_tiled::{tiled, TileExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tiled::Tileset;
+
+use super::GraphicsTileProps;
+use crate::tile::{
+    ButtonCondition,
+    ButtonTriggerTileBundle,
+    LogicKind,
+    LogicTileBundle,
+    SideCondition,
+    TriggerTile,
+    TriggerTileBundle,
+};
+
+/// The types the tilesets can refer to. Newtypes, like [ButtonCondition],
+/// don't get a property type of their own and are stored as what they wrap.
+fn tile_types() -> [&'static TypeInfo; 6] {
+    [
+        LogicKind::type_info(),
+        SideCondition::type_info(),
+        ButtonCondition::type_info(),
+        LogicTileBundle::type_info(),
+        TriggerTileBundle::type_info(),
+        ButtonTriggerTileBundle::type_info(),
+    ]
+}
+
+fn short_name(type_name: &str) -> &str {
+    type_name.rsplit("::").next().unwrap_or(type_name)
+}
+
+/// A custom property type the way Tiled sees it, minus the ID, the color
+/// and the usages, which are up to the designers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyType {
+    /// Only the unit variants are enum values. A variant with data is a
+    /// class of its own, named after the variant.
+    Enum { name: String, values: Vec<String> },
+    Class { name: String, members: Vec<Member> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub name: String,
+    /// The Tiled type: `bool`, `int`, `float`, `string` or `class`
+    pub ty: &'static str,
+    /// The name of the custom type, for enums and classes
+    pub property_type: Option<String>,
+}
+
+impl PropertyType {
+    pub fn name(&self) -> &str {
+        match self {
+            PropertyType::Enum { name, .. } | PropertyType::Class { name, .. } => name,
+        }
+    }
+
+    /// Reads a property type from the project. `None` for the kinds of
+    /// types the game doesn't generate.
+    fn from_json(value: &Value) -> Option<PropertyType> {
+        let name = value["name"].as_str()?.to_owned();
+
+        match value["type"].as_str()? {
+            "enum" => Some(PropertyType::Enum {
+                name,
+                values: value["values"].as_array()?.iter()
+                    .filter_map(|x| x.as_str().map(str::to_owned))
+                    .collect(),
+            }),
+            "class" => Some(PropertyType::Class {
+                name,
+                members: value["members"].as_array()?.iter()
+                    .filter_map(|member| Some(Member {
+                        name: member["name"].as_str()?.to_owned(),
+                        ty: match member["type"].as_str()? {
+                            "bool" => "bool",
+                            "int" => "int",
+                            "float" => "float",
+                            "string" => "string",
+                            "class" => "class",
+                            _ => "unsupported",
+                        },
+                        property_type: member["propertyType"].as_str().map(str::to_owned),
+                    }))
+                    .collect(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Writes the type into the fields of a project entry, leaving the rest
+    /// of the entry as it is.
+    fn write_json(&self, entry: &mut Value, types: &[PropertyType]) {
+        match self {
+            PropertyType::Enum { name, values } => {
+                entry["name"] = json!(name);
+                entry["type"] = json!("enum");
+                entry["storageType"] = json!("string");
+                entry["values"] = json!(values);
+                entry["valuesAsFlags"] = json!(false);
+            },
+            PropertyType::Class { name, members } => {
+                entry["name"] = json!(name);
+                entry["type"] = json!("class");
+                entry["members"] = members.iter()
+                    .map(|member| member.to_json(types))
+                    .collect();
+                if entry.get("color").is_none() { entry["color"] = json!("#ffa0a0a4"); }
+                if entry.get("useAs").is_none() { entry["useAs"] = json!(["property", "tile"]); }
+            },
+        }
+    }
+}
+
+impl Member {
+    fn to_json(&self, types: &[PropertyType]) -> Value {
+        let custom = self.property_type.as_ref()
+            .and_then(|name| types.iter().find(|x| x.name() == name));
+        let value = match (self.ty, custom) {
+            ("bool", _) => json!(false),
+            ("int", _) => json!(0),
+            ("float", _) => json!(0.0),
+            (_, Some(PropertyType::Enum { values, .. })) => json!(values.first()),
+            ("class", _) => json!({}),
+            _ => json!(""),
+        };
+        let mut result = json!({
+            "name": self.name,
+            "type": self.ty,
+            "value": value,
+        });
+        if let Some(property_type) = &self.property_type {
+            result["propertyType"] = json!(property_type);
+        }
+
+        result
+    }
+}
+
+/// Picks the Tiled type for a member. The enums and classes among the tile
+/// types are referred to by name.
+fn member(name: &str, type_name: &str) -> anyhow::Result<Member> {
+    let plain = |ty: &'static str| Ok(Member { name: name.to_owned(), ty, property_type: None });
+    let custom = |ty: &'static str| Ok(Member {
+        name: name.to_owned(),
+        ty,
+        property_type: Some(short_name(type_name).to_owned()),
+    });
+
+    match type_name {
+        "bool" => plain("bool"),
+        "u8" | "u16" | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" => plain("int"),
+        "f32" | "f64" => plain("float"),
+        "alloc::string::String" => plain("string"),
+        _ => match tile_types().into_iter().find(|info| info.type_name() == type_name) {
+            Some(TypeInfo::Enum(_)) => custom("string"),
+            Some(TypeInfo::Struct(_)) => custom("class"),
+            Some(TypeInfo::TupleStruct(info)) if info.field_len() == 1 => {
+                member(name, info.field_at(0).unwrap().type_name())
+            },
+            _ => bail!("Member {name:?} has type {type_name}, which Tiled can't store"),
+        },
+    }
+}
+
+/// Generates the property types from the reflection data of the tile types.
+pub fn generate_property_types() -> anyhow::Result<Vec<PropertyType>> {
+    let mut result = Vec::new();
+
+    for info in tile_types() {
+        let name = short_name(info.type_name()).to_owned();
+
+        match info {
+            TypeInfo::Enum(info) => {
+                let mut values = Vec::new();
+
+                for variant in info.iter() {
+                    let members = match variant {
+                        VariantInfo::Unit(variant) => {
+                            values.push(variant.name().to_owned());
+                            continue;
+                        },
+                        VariantInfo::Tuple(variant) => variant.iter()
+                            .map(|field| member(&field.index().to_string(), field.type_name()))
+                            .collect::<anyhow::Result<_>>(),
+                        VariantInfo::Struct(variant) => variant.iter()
+                            .map(|field| member(field.name(), field.type_name()))
+                            .collect::<anyhow::Result<_>>(),
+                    };
+
+                    result.push(PropertyType::Class {
+                        name: variant.name().to_owned(),
+                        members: members
+                            .with_context(|| format!("While generating {name}::{}", variant.name()))?,
+                    });
+                }
+                result.push(PropertyType::Enum { name, values });
+            },
+            TypeInfo::Struct(info) => result.push(PropertyType::Class {
+                members: info.iter()
+                    .map(|field| member(field.name(), field.type_name()))
+                    .collect::<anyhow::Result<_>>()
+                    .with_context(|| format!("While generating {name}"))?,
+                name,
+            }),
+            _ => (),
+        }
+    }
+
+    Ok(result)
+}
+
+fn property_types_mut(project: &mut Value) -> anyhow::Result<&mut Vec<Value>> {
+    if project.get("propertyTypes").is_none() {
+        project["propertyTypes"] = json!([]);
+    }
+
+    project["propertyTypes"].as_array_mut()
+        .context("The propertyTypes of the project aren't an array")
+}
+
+/// Compares the property types of the project with the generated ones.
+/// Returns a line for each type, which is missing or differs. The types the
+/// game doesn't know about are left alone.
+pub fn property_type_drift(project: &Value) -> anyhow::Result<Vec<String>> {
+    let existing: Vec<_> = project["propertyTypes"].as_array()
+        .map(|types| types.iter().filter_map(PropertyType::from_json).collect())
+        .unwrap_or_default();
+    let mut result = Vec::new();
+
+    for ty in generate_property_types()? {
+        match existing.iter().find(|x| x.name() == ty.name()) {
+            None => result.push(format!("{} is missing", ty.name())),
+            Some(x) if *x != ty => result.push(format!(
+                "{} differs. Expected {ty:?} found {x:?}",
+                ty.name(),
+            )),
+            Some(_) => (),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Puts the generated property types into the project. The existing types
+/// keep their IDs, colors and usages.
+pub fn update_project(project: &mut Value) -> anyhow::Result<()> {
+    let generated = generate_property_types()?;
+    let entries = property_types_mut(project)?;
+    let mut next_id = entries.iter()
+        .filter_map(|entry| entry["id"].as_u64())
+        .max()
+        .unwrap_or_default() + 1;
+
+    for ty in &generated {
+        let entry = match entries.iter().position(|x| x["name"].as_str() == Some(ty.name())) {
+            Some(idx) => &mut entries[idx],
+            None => {
+                entries.push(json!({ "id": next_id }));
+                next_id += 1;
+                entries.last_mut().unwrap()
+            },
+        };
+        ty.write_json(entry, &generated);
+    }
+    entries.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+    Ok(())
+}
+
+fn tile_drift<T: DeserializeOwned>(tileset: &Tileset) -> Vec<String> {
+    tileset.tiles()
+        .filter_map(|(id, tile)| tile.properties::<T>().err().map(|e| format!(
+            "Tile {id} of {} doesn't deserialize: {e}",
+            tileset.name,
+        )))
+        .collect()
+}
+
+/// Deserializes every tile of the tileset with the type the game reads
+/// it as. Returns a line for each tile which fails.
+pub fn tileset_drift(tileset: &Tileset) -> Vec<String> {
+    match tileset.name.as_str() {
+        "logic_tiles" => tile_drift::<LogicTileBundle>(tileset),
+        "activator_tiles" => tile_drift::<TriggerTile>(tileset),
+        "graphics_tiles" => tile_drift::<GraphicsTileProps>(tileset),
+        name => vec![format!("{name} isn't a tileset the game can spawn")],
+    }
+}
+
+/// Loads a `.tsx` file and checks its tiles for drift.
+pub fn check_tileset_file(path: &Path) -> anyhow::Result<Vec<String>> {
+    let tileset = tiled::Loader::new().load_tsx_tileset(path)
+        .with_context(|| format!("Loading {path:?}"))?;
+
+    Ok(tileset_drift(&tileset))
+}
+
+/// Loads a `.tiled-project` file and checks it for drift, along with the
+/// tilesets in the folders of the project.
+pub fn check_project_file(path: &Path) -> anyhow::Result<Vec<String>> {
+    let project = std::fs::read_to_string(path)
+        .with_context(|| format!("Reading {path:?}"))?;
+    let project: Value = serde_json::from_str(&project)?;
+    let mut result = property_type_drift(&project)?;
+
+    let root = path.parent().unwrap_or(Path::new(""));
+    let folders = project["folders"].as_array().into_iter().flatten()
+        .filter_map(Value::as_str);
+    for folder in folders {
+        let dir = root.join(folder);
+        let mut tilesets: Vec<_> = std::fs::read_dir(&dir)
+            .with_context(|| format!("Reading {dir:?}"))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        tilesets.retain(|path| path.extension().and_then(|x| x.to_str()) == Some("tsx"));
+        tilesets.sort();
+
+        for tileset in tilesets {
+            result.extend(check_tileset_file(&tileset)?);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Updates the property types of a `.tiled-project` file in place.
+pub fn update_project_file(path: &Path) -> anyhow::Result<()> {
+    let project = std::fs::read_to_string(path)
+        .with_context(|| format!("Reading {path:?}"))?;
+    let mut project = serde_json::from_str(&project)?;
+    update_project(&mut project)?;
+
+    // Tiled indents its files with 4 spaces
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    project.serialize(&mut serde_json::Serializer::with_formatter(&mut out, formatter))?;
+    std::fs::write(path, out)
+        .with_context(|| format!("Writing {path:?}"))
+}
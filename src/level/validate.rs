@@ -0,0 +1,264 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use bevy_tiled::{tiled, TileLayerExt};
+use serde::Serialize;
+use tiled::{Layer, LayerType, Map, ObjectLayer, TileLayer};
+
+use super::{load_map, read_level_object, read_rules_tile, RulesTile};
+use crate::tile::LogicKind;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in a map. `tile` uses the coordinates Tiled shows,
+/// so designers can find the tile in the editor.
+#[derive(Clone, Debug, Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tile: Option<(i32, i32)>,
+}
+
+/// Loads the map and checks it for problems, which would make the level
+/// fail to load or play incorrectly.
+pub fn validate_map_file(path: &Path) -> Vec<Diagnostic> {
+    let file = path.display().to_string();
+
+    match load_map(path) {
+        Ok(map) => validate_map(&file, &map),
+        Err(e) => vec![Diagnostic {
+            file,
+            severity: Severity::Error,
+            code: "load-failed",
+            message: format!("{e:#}"),
+            layer: None,
+            tile: None,
+        }],
+    }
+}
+
+/// Checks the map for problems. `file` is only used to fill in the
+/// diagnostics.
+pub fn validate_map(file: &str, map: &Map) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        file,
+        diagnostics: Vec::new(),
+        tiles: Vec::new(),
+    };
+
+    for layer in map.layers() {
+        validator.visit_layer(layer);
+    }
+    validator.check_tiles();
+
+    validator.diagnostics
+}
+
+struct Validator<'a> {
+    file: &'a str,
+    diagnostics: Vec<Diagnostic>,
+    tiles: Vec<(String, (i32, i32), RulesTile)>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(
+        &mut self,
+        severity: Severity,
+        code: &'static str,
+        message: String,
+        layer: Option<&str>,
+        tile: Option<(i32, i32)>,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            file: self.file.to_owned(),
+            severity,
+            code,
+            message,
+            layer: layer.map(str::to_owned),
+            tile,
+        });
+    }
+
+    fn visit_layer(&mut self, layer: Layer) {
+        match layer.layer_type() {
+            LayerType::GroupLayer(group) => {
+                group.layers().for_each(|layer| self.visit_layer(layer));
+            },
+            LayerType::TileLayer(tiles) => self.visit_tiles(&layer.name, &tiles),
+            LayerType::ObjectLayer(objects) => self.visit_objects(&layer.name, &objects),
+            LayerType::ImageLayer(_) => (),
+        }
+    }
+
+    fn visit_objects(&mut self, layer_name: &str, layer: &ObjectLayer) {
+        let base_dir = layer.map().source.parent();
+
+        for object in layer.objects() {
+            if let Err(e) = read_level_object(&object, base_dir) {
+                self.report(
+                    Severity::Error,
+                    "bad-object",
+                    format!("Object {:?}: {e:#}", object.name),
+                    Some(layer_name),
+                    None,
+                );
+            }
+        }
+    }
+
+    fn visit_tiles(&mut self, layer_name: &str, layer: &TileLayer) {
+        let tiles = layer.tiles();
+        let is_empty = tiles.is_empty();
+
+        for (pos, tile) in tiles {
+            match read_rules_tile(&tile) {
+                Ok(Some(read)) => self.tiles.push((layer_name.to_owned(), pos, read)),
+                Ok(None) => (),
+                Err(e) => self.report(
+                    Severity::Error,
+                    "bad-tile",
+                    format!("{e:#}"),
+                    Some(layer_name),
+                    Some(pos),
+                ),
+            }
+        }
+
+        if is_empty {
+            self.report(
+                Severity::Warning,
+                "empty-layer",
+                "The layer has no tiles and will fail to spawn".to_owned(),
+                Some(layer_name),
+                None,
+            );
+        }
+    }
+
+    fn check_tiles(&mut self) {
+        let tiles = std::mem::take(&mut self.tiles);
+        let mut logic_positions = HashSet::new();
+        let mut trigger_positions = HashSet::new();
+        let mut starts = Vec::new();
+        let mut exits = Vec::new();
+        let mut buttons = Vec::new();
+        let mut button_triggers = BTreeMap::<u8, Vec<_>>::new();
+
+        for (layer, pos, tile) in &tiles {
+            match tile {
+                RulesTile::Logic(bundle, _) => {
+                    logic_positions.insert(*pos);
+                    match bundle.ty {
+                        LogicKind::Start => starts.push((layer, *pos)),
+                        LogicKind::Exit => exits.push((layer, *pos)),
+                        LogicKind::OnceButton(id) => buttons.push((layer, *pos, id)),
+                        _ => (),
+                    }
+                },
+                RulesTile::ButtonTrigger(cond) => {
+                    trigger_positions.insert(*pos);
+                    button_triggers.entry(cond.0).or_default().push((layer, *pos));
+                },
+                RulesTile::SideTrigger(_) => { trigger_positions.insert(*pos); },
+            }
+        }
+
+        match starts.len() {
+            0 => self.report(
+                Severity::Error,
+                "no-start",
+                "The level has no start tile".to_owned(),
+                None,
+                None,
+            ),
+            1 => (),
+            n => for (layer, pos) in &starts {
+                self.report(
+                    Severity::Error,
+                    "multiple-starts",
+                    format!("The level has {n} start tiles"),
+                    Some(layer.as_str()),
+                    Some(*pos),
+                );
+            },
+        }
+
+        if exits.is_empty() {
+            self.report(
+                Severity::Error,
+                "no-exit",
+                "The level has no exit tile".to_owned(),
+                None,
+                None,
+            );
+        }
+
+        // Exits start inactive, only a trigger can make them winnable
+        for (layer, pos) in &exits {
+            if !trigger_positions.contains(pos) {
+                self.report(
+                    Severity::Error,
+                    "inactive-exit",
+                    "No trigger is placed over the exit, so it never activates".to_owned(),
+                    Some(layer.as_str()),
+                    Some(*pos),
+                );
+            }
+        }
+
+        for (layer, pos, id) in &buttons {
+            if !button_triggers.contains_key(id) {
+                self.report(
+                    Severity::Error,
+                    "unmatched-button",
+                    format!("No trigger listens to button {id}"),
+                    Some(layer.as_str()),
+                    Some(*pos),
+                );
+            }
+        }
+
+        for (id, triggers) in &button_triggers {
+            if buttons.iter().any(|(_, _, button_id)| button_id == id) {
+                continue;
+            }
+
+            for (layer, pos) in triggers {
+                self.report(
+                    Severity::Warning,
+                    "unused-button-trigger",
+                    format!("There is no button {id} to activate the trigger"),
+                    Some(layer.as_str()),
+                    Some(*pos),
+                );
+            }
+        }
+
+        for (layer, pos, tile) in &tiles {
+            let is_trigger = matches!(
+                tile,
+                RulesTile::SideTrigger(_) | RulesTile::ButtonTrigger(_),
+            );
+
+            if is_trigger && !logic_positions.contains(pos) {
+                self.report(
+                    Severity::Error,
+                    "dangling-trigger",
+                    "The trigger isn't placed over a logic tile".to_owned(),
+                    Some(layer.as_str()),
+                    Some(*pos),
+                );
+            }
+        }
+    }
+}
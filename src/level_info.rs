@@ -13,3 +13,8 @@ impl LevelInfo {
         self.level_counts[world as usize]
     }
 }
+
+/// The path to the map of the level.
+pub fn level_path(world: u8, level: u8) -> String {
+    format!("maps/level{}-{}.tmx", world, level)
+}
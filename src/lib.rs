@@ -1,11 +1,18 @@
+mod achievement;
+mod action;
+mod headless;
+mod history;
 mod level;
 mod moveable;
 mod states;
 mod player;
+mod replay;
 mod save;
+mod settings;
 mod tile;
 mod level_info;
 mod config;
+pub mod testing;
 
 use bevy::render::camera::{WindowOrigin, ScalingMode};
 use states::setup_states;
@@ -15,19 +22,43 @@ use bevy_pkv::PkvStore;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_common_assets::json::JsonAssetPlugin;
 
+use achievement::{AchievementList, AchievementPlugin};
+use action::ActionPlugin;
+use history::HistoryPlugin;
 use moveable::MoveablePlugin;
 use level_info::LevelInfo;
 use level::LevelPlugin;
 use player::PlayerPlugin;
+use replay::{ReplayPlayback, ReplayPlugin};
+#[cfg(not(target_arch = "wasm32"))]
+use replay::ReplayRecording;
+use settings::{Settings, SettingsPlugin};
 
 pub use config::*;
-
-#[cfg(target_arch = "x86_64")] use bevy_framepace::{ FramepacePlugin, FramepaceSettings, Limiter };
+pub use replay::Replay;
+pub use headless::{ play_headless, HeadlessLevel, PlayOutcome };
+pub use level::{
+    load_layout,
+    load_level_state,
+    layout_from_map,
+    check_project_file,
+    check_tileset_file,
+    generate_property_types,
+    update_project_file,
+    validate_map,
+    validate_map_file,
+    Diagnostic,
+    Member,
+    PropertyType,
+    Severity,
+};
+
+#[cfg(target_arch = "x86_64")] use bevy_framepace::{ FramepacePlugin, FramepaceSettings };
 
 #[derive(Clone, Copy, Component)]
 pub struct GameplayCamera;
 
-fn window_descriptor() -> WindowDescriptor {
+fn window_descriptor(settings: &Settings) -> WindowDescriptor {
     WindowDescriptor {
         title: LAUNCHER_TITLE.to_owned(),
         resizable: false,
@@ -35,6 +66,8 @@ fn window_descriptor() -> WindowDescriptor {
         height: WINDOW_HEIGHT,
         canvas: Some("#bevy".to_string()),
         fit_canvas_to_parent: true,
+        present_mode: settings.present_mode(),
+        mode: settings.window_mode(),
         ..Default::default()
     }
 }
@@ -43,21 +76,24 @@ pub fn app(params: LaunchParams) -> App {
     let mut app = App::new();
 
     // Insert base resources
+    let pkv = PkvStore::new(DEV_NAME, GAME_NAME);
+    let settings = Settings::load(&pkv);
     app
-        .insert_resource(PkvStore::new(DEV_NAME, GAME_NAME))
+        .insert_resource(pkv)
+        .insert_resource(settings.clone())
         .insert_resource(ClearColor(Color::hex("263238").unwrap()));
 
     // Load bevy's core
     DefaultPlugins
         .set(WindowPlugin {
-            window: window_descriptor(),
+            window: window_descriptor(&settings),
             ..default()
         })
         .finish(&mut app);
 
     // Load framepace
     app
-        .insert_resource(FramepaceSettings::default().with_limiter(Limiter::from_framerate(60.0)))
+        .insert_resource(FramepaceSettings::default().with_limiter(settings.limiter()))
         .add_plugin(FramepacePlugin);
 
     // Init or not init inspector (DO IT BEFORE THE GAME PLUGINS)
@@ -67,10 +103,24 @@ pub fn app(params: LaunchParams) -> App {
 
     // Game plugins
     app
+        .add_plugin(SettingsPlugin)
+        .add_plugin(ActionPlugin)
         .add_plugin(JsonAssetPlugin::<LevelInfo>::new(&["level-info"]))
+        .add_plugin(JsonAssetPlugin::<AchievementList>::new(&["achievements"]))
         .add_plugin(MoveablePlugin)
         .add_plugin(LevelPlugin)
-        .add_plugin(PlayerPlugin);
+        .add_plugin(PlayerPlugin)
+        .add_plugin(HistoryPlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(AchievementPlugin);
+
+    if let Some(replay) = &params.replay {
+        app.insert_resource(ReplayPlayback::new(replay.clone()));
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(dir) = params.replay_dir {
+        app.insert_resource(ReplayRecording { dir: dir.into() });
+    }
 
 
     let camera_bundle = Camera2dBundle {
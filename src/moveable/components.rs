@@ -6,13 +6,13 @@ pub use cube_rot::*;
 
 /// Tracks moveable's rotation. This component has not public
 /// API and is used by the systems internally.
-#[derive(Debug, Clone, Copy, Default, Component)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Component)]
 #[repr(transparent)]
 pub struct Rotation(pub (super) DecomposedRotation);
 
 /// Tracks moveable's position. This component has not public
 /// API and is used by the systems internally.
-#[derive(Debug, Clone, Copy, Default, Component)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Component)]
 #[repr(transparent)]
 pub struct Position(pub (super) TilePos);
 
@@ -84,6 +84,31 @@ impl MoveableBundle {
     }
 }
 
+/// A copy of moveable's position and rotation. It can be put back onto
+/// the moveable with [MoveableQueryItem::restore] or turned into a new
+/// moveable with [MoveableSnapshot::bundle].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MoveableSnapshot {
+    position: Position,
+    rotation: Rotation,
+}
+
+impl MoveableSnapshot {
+    pub fn tile_pos(&self) -> TilePos {
+        self.position.0
+    }
+
+    /// Creates an idle moveable in the snapshotted state.
+    pub fn bundle(&self) -> MoveableBundle {
+        MoveableBundle {
+            position: self.position,
+            rotation: self.rotation,
+            side: Side::Ready(self.rotation.0.upper_side()),
+            state: MoveableState::Idle,
+        }
+    }
+}
+
 /// Tag for tilemap, which moveables are intended to traverse.
 #[derive(Default, Clone, Copy, Debug, Component)]
 pub struct MoveableTilemapTag;
@@ -110,6 +135,25 @@ impl<'a> MoveableQueryItem<'a> {
         *self.state = MoveableState::Idle;
     }
 
+    /// Copies the position and rotation of the moveable.
+    pub fn snapshot(&self) -> MoveableSnapshot {
+        MoveableSnapshot {
+            position: *self.position,
+            rotation: *self.rotation,
+        }
+    }
+
+    /// Puts the moveable back into the snapshotted state, cancelling
+    /// any movement.
+    ///
+    /// **WARNING:** just like [MoveableQueryItem::force_idle], this will
+    /// announce to other systems that the side has been set to `Ready(..)`.
+    pub fn restore(&mut self, snapshot: MoveableSnapshot) {
+        *self.position = snapshot.position;
+        *self.rotation = snapshot.rotation;
+        self.force_idle();
+    }
+
     /// Returns the progress of the movement of a moveable.
     /// If the moveable is idle, `None` is returned.
     pub fn movement_progress(&self) -> Option<f32> {
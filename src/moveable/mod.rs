@@ -1,12 +1,13 @@
 mod components;
 mod systems;
-mod events;
 
-pub use events::*;
 pub use components::*;
 
 pub use systems::*;
 use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::states::PauseState;
 //use bevy_inspector_egui::{ RegisterInspectable, InspectableRegistry };
 
 #[derive(StageLabel)]
@@ -26,9 +27,8 @@ pub struct MoveablePlugin;
 impl Plugin for MoveablePlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_event::<TileInteractionEvent>()
             .add_stage_after(CoreStage::Update, MoveableUpdateStage, SystemStage::parallel())
             .add_system_to_stage(MoveableUpdateStage, moveable_animation.label(MoveableSystem::Animate))
-            .add_system_to_stage(MoveableUpdateStage, moveable_tick.label(MoveableSystem::Tick).before(MoveableSystem::Animate));
+            .add_system_to_stage(MoveableUpdateStage, moveable_tick.run_in_state(PauseState::Running).label(MoveableSystem::Tick).before(MoveableSystem::Animate));
     }
 }
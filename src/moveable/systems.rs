@@ -1,44 +1,34 @@
 use std::time::Duration;
-use super::events::*;
 use super::components::*;
 use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::*;
+use crate::settings::Settings;
 
 pub const MOVEABLE_Z_POS: f32 = 101f32;
 
-/// Updates the internals of a moveable. Returns an iteraction event if one should
-/// be posted.
+/// Updates the internals of a moveable. The tiles act on the moveables
+/// through the rules, not here.
 fn update_moveable(
-    moveable_id: Entity,
     item: &mut MoveableQueryItem,
     tiles: &TileStorage,
     dt: Duration,
-) -> Option<TileInteractionEvent> {
+) {
     match &mut *item.state {
-        // No interactions when idle
-        MoveableState::Idle => None,
-        // Moving objects might attempt interacting
+        MoveableState::Idle => (),
         MoveableState::Moving { timer, ty } => match &ty {
-            // If we are rotating -- we don't interact with the tile
             MoveTy::Rotate { clock_wise } => {
                 // Update the rotation and become idle once the timer ticks down
                 if timer.tick(dt).just_finished() {
                     item.rotation.0 = item.rotation.0.rotate_ortho(*clock_wise);
                     *item.state = MoveableState::Idle;
                 }
-
-                None
             },
-            // If we are actually changing our current tile, we will interact
             MoveTy::Slide { dir, next_pos } => {
-                // Verify the presence of the tile and the validity of the ID
-                let tile_id = match tiles.get(next_pos) {
-                    Some(x) => x,
-                    None => {
-                        item.force_idle();
-                        return None;
-                    },
-                };
+                // Verify the presence of the tile
+                if tiles.get(next_pos).is_none() {
+                    item.force_idle();
+                    return;
+                }
 
                 // When we have finished moving, update our pos and rotation (if needed)
                 if timer.tick(dt).just_finished() {
@@ -50,10 +40,6 @@ fn update_moveable(
 
                     item.position.0 = *next_pos;
                     *item.state = MoveableState::Idle;
-
-                    Some(TileInteractionEvent { moveable_id, tile_id })
-                } else {
-                    None
                 }
             },
         }
@@ -66,25 +52,20 @@ fn update_moveable(
 /// * Cancels all attempts to move (force-transitioning all moveables into `Idle` style)
 /// if they are trying to move into a tile that doesn't exist.
 /// * Tick all timers on moveables.
-/// * Issue `TileInteractionEvent` when a moveable is done moving onto a tile.
 /// * Performs state transitions on the moveables when they are done moving.
 pub fn moveable_tick(
-    mut interaction_events: EventWriter<TileInteractionEvent>,
-    mut moveable_q: Query<(Entity, MoveableQuery)>,
+    mut moveable_q: Query<MoveableQuery>,
     map_q: Query<&TileStorage, With<MoveableTilemapTag>>,
     time: Res<Time>,
+    settings: Res<Settings>,
 ) {
-    let dt = time.delta();
+    let dt = time.delta().mul_f32(settings.animation_speed);
     let tiles = match map_q.get_single() {
         Ok(x) => x,
         Err(_) => return,
     };
 
-    moveable_q.for_each_mut(|(id, mut moveable)| {
-        if let Some(e) = update_moveable(id, &mut moveable, tiles, dt) {
-            interaction_events.send(e);
-        }
-    });
+    moveable_q.for_each_mut(|mut moveable| update_moveable(&mut moveable, tiles, dt));
 }
 
 // TODO Maybe doing animations this way is bad
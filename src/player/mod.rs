@@ -9,8 +9,9 @@ use iyes_loopless::prelude::*;
 
 use crate::level::tile_pos_to_world_pos;
 use crate::moveable::{MoveableBundle, MoveableTilemapTag, self};
-use crate::states::GameState;
-use crate::tile::{LogicKind, TileUpdateStage};
+use crate::states::{GameState, PauseState};
+use crate::tile::{ButtonCondition, LevelRules, LogicKind, SideCondition, TileUpdateStage};
+use puzzle_rules::{LevelError, Pos};
 
 pub use components::*;
 pub use resources::*;
@@ -22,25 +23,23 @@ pub struct PlayerInputStage;
 #[derive(StageLabel, Debug, PartialEq, Eq, Hash, Clone)]
 pub struct PlayerPostStage;
 
+#[derive(SystemLabel, Debug, PartialEq, Eq, Hash, Clone)]
+pub enum PlayerSystem {
+    Controls,
+}
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
+        add_player_controls(app);
+
         app
-            .add_stage_after(
-                TileUpdateStage,
-                PlayerInputStage,
-                SystemStage::parallel(),
-            )
             .add_stage_before(
                 CoreStage::PostUpdate,
                 PlayerPostStage,
                 SystemStage::parallel(),
             )
-            .add_system_to_stage(
-                PlayerInputStage,
-                player_controls.run_in_state(GameState::InGame),
-            )
             .add_system_set_to_stage(
                 PlayerPostStage,
                 ConditionSet::new()
@@ -53,61 +52,111 @@ impl Plugin for PlayerPlugin {
     }
 }
 
-fn find_start_pos(start_q: Query<(&TilePos, &LogicKind)>) -> Option<TilePos> {
-    if start_q
-        .iter()
-        .filter(|(_, kind)| matches!(kind, LogicKind::Start))
-        .count()
-        > 1
-    {
+/// Adds the controls of the player, which are the only part of the player
+/// an app without a window needs.
+pub fn add_player_controls(app: &mut App) {
+    app
+        .add_stage_after(
+            TileUpdateStage,
+            PlayerInputStage,
+            SystemStage::parallel(),
+        )
+        .add_system_to_stage(
+            PlayerInputStage,
+            player_controls
+                .run_in_state(GameState::InGame)
+                .run_in_state(PauseState::Running)
+                .run_if_resource_exists::<LevelRules>()
+                .label(PlayerSystem::Controls),
+        );
+}
+
+/// Spawns the player on the start tile and hands the level over to the
+/// rules. The player has no graphics yet, [add_player_graphics] gives it
+/// its cube, so that an app without a window can spawn the player too.
+pub fn spawn_player(
+    mut commands: Commands,
+    logic_tile_q: Query<(&TilePos, &LogicKind, &TileFlip)>,
+    side_trigger_q: Query<(&TilePos, &SideCondition)>,
+    button_trigger_q: Query<(&TilePos, &ButtonCondition)>,
+) -> Result<(Entity, TilePos), LevelError> {
+    if logic_tile_q.iter().filter(|(_, kind, _)| matches!(kind, LogicKind::Start)).count() > 1 {
         warn!("This level has more than one player start. Make sure, that your map file is correct.");
     }
 
-    start_q
-        .iter()
-        .find(|(_, kind)| matches!(kind, LogicKind::Start))
-        .map(|(pos, _)| *pos)
+    let rules = LevelRules::from_tiles(&logic_tile_q, &side_trigger_q, &button_trigger_q)?;
+    let Pos { x, y } = rules.state().player().pos;
+    let start_pos = TilePos { x, y };
+
+    let player = commands
+        .spawn((
+            PlayerTag,
+            Name::new("Player"),
+            MoveableBundle::new(start_pos),
+        ))
+        .id();
+    commands.insert_resource(rules);
+
+    Ok((player, start_pos))
 }
 
-pub fn spawn_player(
+/// Gives the player spawned by [spawn_player] its cube.
+pub fn add_player_graphics(
+    In(spawned): In<Result<(Entity, TilePos), LevelError>>,
     mut commands: Commands,
-    start_q: Query<(&TilePos, &LogicKind)>,
     map_q: Query<(&Transform, &TilemapGridSize), With<MoveableTilemapTag>>,
     generated_assets: Res<GeneratedPlayerAssets>,
 ) {
-    let (map_tf, map_grid) = match map_q.get_single() {
+    let (player, start_pos) = match spawned {
         Ok(x) => x,
         Err(e) => {
-            error!("Failed to query the level map: {}", e);
+            error!("Failed to set up the rules of the level: {}", e);
             return;
         }
     };
-
-    let start_pos = match find_start_pos(start_q) {
-        Some(x) => x,
-        None => {
-            error!("Start tile not found");
+    let (map_tf, map_grid) = match map_q.get_single() {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Failed to query the level map: {}", e);
             return;
         }
     };
 
     let start_world_pos = tile_pos_to_world_pos(start_pos, map_tf, map_grid);
+    commands.entity(player).insert(player_graphics(start_world_pos, &generated_assets));
+}
 
+/// Spawns the player's cube. `world_pos` must match the position
+/// of the `moveable`.
+pub fn spawn_player_entity(
+    commands: &mut Commands,
+    moveable: MoveableBundle,
+    world_pos: Vec2,
+    generated_assets: &GeneratedPlayerAssets,
+) -> Entity {
     commands
         .spawn((
             PlayerTag,
             Name::new("Player"),
-            MoveableBundle::new(start_pos),
-            MaterialMesh2dBundle {
-                mesh: generated_assets.model.clone(),
-                material: generated_assets.material.clone(),
-                // TODO hardcoded player size
-                // FIXME feels weird to double-set player's pos
-                transform: Transform::from_translation(
-                    start_world_pos.extend(moveable::MOVEABLE_Z_POS),
-                )
-                .with_scale(Vec3::new(16.0f32, 16.0f32, 16.0f32)),
-                ..default()
-            }
-        ));
+            moveable,
+            player_graphics(world_pos, generated_assets),
+        ))
+        .id()
+}
+
+fn player_graphics(
+    world_pos: Vec2,
+    generated_assets: &GeneratedPlayerAssets,
+) -> MaterialMesh2dBundle<ColorMaterial> {
+    MaterialMesh2dBundle {
+        mesh: generated_assets.model.clone(),
+        material: generated_assets.material.clone(),
+        // TODO hardcoded player size
+        // FIXME feels weird to double-set player's pos
+        transform: Transform::from_translation(
+            world_pos.extend(moveable::MOVEABLE_Z_POS),
+        )
+        .with_scale(Vec3::new(16.0f32, 16.0f32, 16.0f32)),
+        ..default()
+    }
 }
@@ -1,14 +1,16 @@
 use bevy::prelude::*;
 use cube_rot::MoveDirection;
-use std::time::Duration;
 use crate::GameplayCamera;
-use crate::moveable::{ MoveableQuery, MoveableQueryItem };
-use crate::tile::TileEvent;
+use crate::action::{ Action, ActionAccepted, ActionInput };
+use crate::settings::Settings;
+use crate::moveable::MoveableQuery;
+use crate::tile::{ LevelRules, TileEvent };
 use super::{ PlayerTag, PlayerWinnerTag, BasePlayerAssets };
 
 pub fn player_win_sound(
     audio: Res<Audio>,
     assets: Res<BasePlayerAssets>,
+    settings: Res<Settings>,
     mut tile_events: EventReader<TileEvent>,
 ) {
     if tile_events.iter().filter_map(|x| match x {
@@ -16,7 +18,10 @@ pub fn player_win_sound(
             _ => None,
         }).next().is_some()
     {
-        audio.play(assets.complete_sound.clone());
+        audio.play_with_settings(
+            assets.complete_sound.clone(),
+            PlaybackSettings::ONCE.with_volume(settings.effective_sfx_volume()),
+        );
     }
 }
 
@@ -52,28 +57,10 @@ pub fn player_camera(
 #[derive(Default)]
 pub struct InputQueue(Option<MoveDirection>);
 
-fn check_input(key_input: &Res<Input<KeyCode>>) -> Option<MoveDirection> {
-    if key_input.pressed(KeyCode::W) {
-        return Some(MoveDirection::Up);
-    }
-
-    if key_input.pressed(KeyCode::A) {
-        return Some(MoveDirection::Left);
-    }
-
-    if key_input.pressed(KeyCode::S) {
-        return Some(MoveDirection::Down);
-    }
-
-    if key_input.pressed(KeyCode::D) {
-        return Some(MoveDirection::Right);
-    }
-
-    None
-}
-
-fn player_flip(m: &mut MoveableQueryItem, dir: MoveDirection) -> bool {
-    m.flip(dir, Duration::from_secs_f32(0.52f32))
+fn check_input(actions: &ActionInput) -> Option<MoveDirection> {
+    Action::MOVES.into_iter()
+        .find(|action| actions.pressed(*action))
+        .and_then(Action::move_direction)
 }
 
 /// The system for controlling the player. The system implements input
@@ -88,11 +75,13 @@ fn player_flip(m: &mut MoveableQueryItem, dir: MoveDirection) -> bool {
 /// pressing different keys.
 /// 3. After the animation is over, the queued up input gets dequeued.
 ///
-/// If the cube isn't rolling -- the input isn't queued up and gets applied
-/// right away.
+/// If the cube isn't rolling -- the input isn't queued up and gets handed
+/// to the [LevelRules] right away.
 pub fn player_controls(
     mut queue: Local<InputQueue>,
-    key_input: Res<Input<KeyCode>>,
+    actions: Res<ActionInput>,
+    mut accepted: EventWriter<ActionAccepted>,
+    mut rules: ResMut<LevelRules>,
     mut query: Query<MoveableQuery, With<PlayerTag>>,
 ) {
     /*
@@ -100,27 +89,29 @@ pub fn player_controls(
         any keys.
     */
     let queue_input = queue.0.take();
-    let input = match check_input(&key_input).or(queue_input) {
+    let input = match check_input(&actions).or(queue_input) {
         Some(x) => x,
         None => return,
     };
 
-    let mut player = match query.get_single_mut() {
+    let player = match query.get_single_mut() {
         Ok(x) => x,
         Err(_) => return,
     };
 
     /*
-        If we fail to apply the input -- it gets queued up.
+        If the player is still busy -- the input gets queued up.
 
         Note, that it allows the input to come back into the queue.
     */
-    if !player_flip(&mut player, input) {
+    if player.is_moving() || !rules.is_settled() {
         match player.movement_progress() {
             Some(x) if x >= 0.65f32 => {
                 queue.0  = Some(input);
             },
             _ => (),
         }
+    } else if rules.roll(input) {
+        accepted.send(ActionAccepted(Action::from_move_direction(input)));
     }
 }
@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use anyhow::Context;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::action::{Action, ActionAccepted, ActionInput, ActionUpdate};
+use crate::level::BaseLevelAssets;
+use crate::moveable::MoveableQuery;
+use crate::player::PlayerTag;
+use crate::states::{GameState, PauseState};
+use crate::tile::LevelRules;
+use crate::VERSION;
+
+/// A recorded level attempt. Only the actions the game has accepted are
+/// recorded, so playing a replay back doesn't depend on the frame timing
+/// or on the input queueing.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    /// The asset path of the map
+    pub map: String,
+    /// The version of the game the replay was recorded with
+    pub version: String,
+    /// The accepted actions and the simulation ticks they were accepted on
+    pub inputs: Vec<(u64, Action)>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Opening {path:?}"))?;
+
+        serde_json::from_reader(std::io::BufReader::new(file))
+            .with_context(|| format!("Parsing {path:?}"))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Creating {dir:?}"))?;
+        }
+
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Creating {path:?}"))?;
+
+        serde_json::to_writer(file, self)
+            .with_context(|| format!("Writing {path:?}"))
+    }
+}
+
+/// The amount of frames the level has been played for. Doesn't advance
+/// while the game is paused.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct SimulationTick(pub u64);
+
+/// The directory to record the level attempts into. Without it nothing
+/// gets recorded. The web build has no files to record into.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Resource, Clone, Debug)]
+pub struct ReplayRecording {
+    pub dir: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Resource, Default)]
+struct ReplayRecorder {
+    inputs: Vec<(u64, Action)>,
+}
+
+/// The actions left to play back. While this resource is present, the
+/// player's own input is ignored (except for the pause).
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    inputs: VecDeque<(u64, Action)>,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayback { inputs: replay.inputs.into() }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.inputs.is_empty()
+    }
+}
+
+fn reset_tick(mut commands: Commands) {
+    commands.insert_resource(SimulationTick::default());
+}
+
+fn advance_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn reset_recording(mut commands: Commands) {
+    commands.insert_resource(ReplayRecorder::default());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn record_actions(
+    tick: Res<SimulationTick>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut accepted: EventReader<ActionAccepted>,
+) {
+    for ActionAccepted(action) in accepted.iter() {
+        recorder.inputs.push((tick.0, *action));
+    }
+}
+
+/// Presses the next action once its tick has come. The action is pressed
+/// only when the player is at rest and the rules have played back the
+/// previous roll, so it is guaranteed to be accepted.
+fn play_back_actions(
+    tick: Res<SimulationTick>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut actions: ResMut<ActionInput>,
+    rules: Res<LevelRules>,
+    mut player_q: Query<MoveableQuery, With<PlayerTag>>,
+) {
+    actions.retain(|action| action == Action::Pause);
+
+    let (at, action) = match playback.inputs.front() {
+        Some(x) => *x,
+        None => return,
+    };
+    if tick.0 < at { return; }
+
+    let can_act = match player_q.get_single_mut() {
+        Ok(player) => !player.is_moving() && rules.is_settled(),
+        // Only an undo can bring a destroyed player back
+        Err(_) => action == Action::Undo,
+    };
+    if !can_act { return; }
+
+    actions.press(action);
+    playback.inputs.pop_front();
+}
+
+/// Writes the recorded attempt into the directory of the recording.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_replay(
+    recorder: &mut ReplayRecorder,
+    recording: &ReplayRecording,
+    asset_server: &AssetServer,
+    base_level_assets: &BaseLevelAssets,
+) {
+    let inputs = std::mem::take(&mut recorder.inputs);
+    if inputs.is_empty() { return; }
+
+    let map = match asset_server.get_handle_path(&base_level_assets.map) {
+        Some(x) => x.path().to_string_lossy().into_owned(),
+        None => {
+            warn!("Can't record the replay: the map has no path");
+            return;
+        }
+    };
+    let replay = Replay {
+        map,
+        version: VERSION.to_owned(),
+        inputs,
+    };
+
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default();
+    let stem = Path::new(&replay.map)
+        .file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let path = recording.dir.join(format!("{stem}-{time}.replay"));
+
+    match replay.save(&path) {
+        Ok(()) => info!("Recorded the replay into {path:?}"),
+        Err(e) => error!("Failed to record the replay: {e:#}"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_replay_on_exit(
+    mut recorder: ResMut<ReplayRecorder>,
+    recording: Option<Res<ReplayRecording>>,
+    asset_server: Res<AssetServer>,
+    base_level_assets: Option<Res<BaseLevelAssets>>,
+) {
+    if let (Some(recording), Some(base_level_assets)) = (recording, base_level_assets) {
+        write_replay(&mut recorder, &recording, &asset_server, &base_level_assets);
+    }
+}
+
+/// The game can be closed from inside of a level (for example when testing
+/// a level), so the exit systems of the state won't run.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_replay_on_app_exit(
+    recorder: ResMut<ReplayRecorder>,
+    recording: Option<Res<ReplayRecording>>,
+    asset_server: Res<AssetServer>,
+    base_level_assets: Option<Res<BaseLevelAssets>>,
+    exit_events: EventReader<AppExit>,
+) {
+    if !exit_events.is_empty() {
+        write_replay_on_exit(recorder, recording, asset_server, base_level_assets);
+    }
+}
+
+/// Plays back the [ReplayPlayback], if there's one. Outside of the web
+/// build it also records every level attempt, if there's a
+/// [ReplayRecording].
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SimulationTick>()
+            .add_enter_system(GameState::InGame, reset_tick)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                advance_tick
+                    .run_in_state(GameState::InGame)
+                    .run_in_state(PauseState::Running)
+                    .before(ActionUpdate),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                play_back_actions
+                    .run_in_state(GameState::InGame)
+                    .run_in_state(PauseState::Running)
+                    .run_if_resource_exists::<ReplayPlayback>()
+                    .run_if_resource_exists::<LevelRules>()
+                    .after(ActionUpdate),
+            );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app
+            .init_resource::<ReplayRecorder>()
+            .add_enter_system(GameState::InGame, reset_recording)
+            .add_exit_system(GameState::InGame, write_replay_on_exit)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                record_actions
+                    .run_in_state(GameState::InGame)
+                    .run_if_resource_exists::<ReplayRecording>()
+                    .run_unless_resource_exists::<ReplayPlayback>(),
+            )
+            .add_system_to_stage(
+                CoreStage::Last,
+                write_replay_on_app_exit.run_in_state(GameState::InGame),
+            );
+    }
+}
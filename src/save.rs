@@ -1,13 +1,40 @@
-use bevy::prelude::Resource;
-use std::collections::HashSet;
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use std::collections::{HashMap, HashSet};
 
 use crate::level_info::LevelInfo;
 
+/// The best results the player has shown on a level. The moves and the
+/// time are tracked separately, so they may come from different attempts.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct LevelRecord {
+    pub moves: u32,
+    /// Seconds spent on the level
+    pub time: f32,
+}
+
+/// Lifetime totals, which the achievements are checked against.
+#[derive(Clone, Copy, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Counters {
+    pub deaths: u32,
+    pub moves: u32,
+    pub button_presses: u32,
+}
+
 #[derive(Resource, Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Save {
     achievements: HashSet<String>,
     world: u8, // The world player has yet to beat
     level: u8, // The level the player has yet to beat
+    // Keyed by "world-level"
+    #[serde(default)]
+    records: HashMap<String, LevelRecord>,
+    #[serde(default)]
+    counters: Counters,
+}
+
+fn record_key(world: u8, level: u8) -> String {
+    format!("{world}-{level}")
 }
 
 impl Save {
@@ -16,18 +43,62 @@ impl Save {
             achievements: HashSet::new(),
             world: 0,
             level: 0,
+            records: HashMap::new(),
+            counters: Counters::default(),
+        }
+    }
+
+    /// Writes the save into the store.
+    pub fn store(&self, pkv: &mut PkvStore) {
+        // TODO retry?
+        match pkv.set("save", self) {
+            Ok(()) => (),
+            Err(e) => error!("Error recording save: {}\nThe progress will be lost.", e),
         }
     }
 
     pub fn world_level(&self) -> (u8, u8) { (self.world, self.level) }
 
-    /*
-    pub fn give_achievement(&mut self, name: &str) { self.achievements.insert(name.to_owned()); }
+    /// Returns `true` if the achievement hasn't been given before.
+    pub fn give_achievement(&mut self, name: &str) -> bool { self.achievements.insert(name.to_owned()) }
 
     pub fn has_achievement(&self, name: &str) -> bool { self.achievements.contains(name) }
-    */
 
-    pub fn register_level_complete(&mut self, info: &LevelInfo) {
+    pub fn counters(&self) -> &Counters { &self.counters }
+
+    pub fn counters_mut(&mut self) -> &mut Counters { &mut self.counters }
+
+    /// All levels up to the one the player has yet to beat are playable.
+    pub fn is_unlocked(&self, world: u8, level: u8) -> bool {
+        (world, level) <= (self.world, self.level)
+    }
+
+    pub fn is_complete(&self, world: u8, level: u8) -> bool {
+        (world, level) < (self.world, self.level) ||
+        self.records.contains_key(&record_key(world, level))
+    }
+
+    pub fn record(&self, world: u8, level: u8) -> Option<&LevelRecord> {
+        self.records.get(&record_key(world, level))
+    }
+
+    /// Updates the best results of the level. Moves the player on to the
+    /// next level, if the level was the one they had yet to beat.
+    pub fn register_level_complete(
+        &mut self,
+        info: &LevelInfo,
+        (world, level): (u8, u8),
+        record: LevelRecord,
+    ) {
+        self.records.entry(record_key(world, level))
+            .and_modify(|best| {
+                best.moves = best.moves.min(record.moves);
+                best.time = best.time.min(record.time);
+            })
+            .or_insert(record);
+
+        if (world, level) != (self.world, self.level) { return; }
+
         if let Some((world, level)) = self.next_level(info) {
             self.world = world;
             self.level = level;
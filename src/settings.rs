@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use bevy::window::{PresentMode, WindowMode};
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::action::Bindings;
+
+#[cfg(target_arch = "x86_64")] use bevy_framepace::{ FramepaceSettings, Limiter };
+
+/// The player's preferences. They are stored next to the save and are
+/// applied as soon as they change.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    /// Frames per second. `0` means there's no limit.
+    pub frame_limit: u32,
+    pub vsync: bool,
+    pub fullscreen: bool,
+    /// How fast the moveables move, `1.0` being the normal speed.
+    pub animation_speed: f32,
+    pub bindings: Bindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            master_volume: 1.0f32,
+            sfx_volume: 1.0f32,
+            frame_limit: 60,
+            vsync: true,
+            fullscreen: false,
+            animation_speed: 1.0f32,
+            bindings: Bindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings from the store, falling back to the defaults.
+    pub fn load(pkv: &PkvStore) -> Self {
+        pkv.get::<Settings>("settings").unwrap_or_default()
+    }
+
+    pub fn store(&self, pkv: &mut PkvStore) {
+        match pkv.set("settings", self) {
+            Ok(()) => (),
+            Err(e) => error!("Error recording settings: {}", e),
+        }
+    }
+
+    /// The volume to play the sound effects at.
+    pub fn effective_sfx_volume(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync }
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen { WindowMode::BorderlessFullscreen } else { WindowMode::Windowed }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    pub fn limiter(&self) -> Limiter {
+        match self.frame_limit {
+            0 => Limiter::Off,
+            fps => Limiter::from_framerate(fps as f64),
+        }
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut windows: ResMut<Windows>,
+    mut pkv: ResMut<PkvStore>,
+    #[cfg(target_arch = "x86_64")] mut framepace: ResMut<FramepaceSettings>,
+) {
+    if !settings.is_changed() { return; }
+
+    if let Some(window) = windows.get_primary_mut() {
+        if window.present_mode() != settings.present_mode() {
+            window.set_present_mode(settings.present_mode());
+        }
+        if window.mode() != settings.window_mode() {
+            window.set_mode(settings.window_mode());
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        framepace.limiter = settings.limiter();
+    }
+
+    settings.store(&mut pkv);
+}
+
+/// Applies and stores the [Settings]. The resource itself must be
+/// inserted before the plugin.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PostUpdate, apply_settings);
+    }
+}
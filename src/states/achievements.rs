@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use super::{ GameState, MenuAssets };
+use crate::achievement::AchievementList;
+use crate::save::Save;
+use crate::{GameplayCamera, LaunchParams};
+
+#[derive(Clone, Copy, Component)]
+struct BackButton;
+
+fn spawn_text(
+    commands: &mut Commands,
+    save: &Save,
+    list: Option<&AchievementList>,
+    menu_assets: &MenuAssets,
+) {
+    let font = menu_assets.main_font.clone();
+    let text_style = |font_size, color| TextStyle {
+        font: font.clone(),
+        font_size,
+        color,
+    };
+
+    // Root node
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            // Header
+            parent
+                .spawn(TextBundle {
+                    style: Style {
+                        margin: UiRect {
+                            top: Val::Px(20.0),
+                            bottom: Val::Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    text: Text::from_section("Achievements", text_style(50.0f32, Color::WHITE))
+                        .with_alignment(TextAlignment::CENTER),
+                    ..default()
+                });
+
+            let achievements = list.map(|x| x.achievements.as_slice()).unwrap_or_default();
+            for achievement in achievements {
+                let unlocked = save.has_achievement(&achievement.id);
+                let (background, color) = match unlocked {
+                    true => (Color::rgb(0.0, 0.3, 0.2), Color::WHITE),
+                    false => (Color::rgb(0.1, 0.1, 0.1), Color::GRAY),
+                };
+
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            size: Size::new(Val::Px(600.0), Val::Auto),
+                            padding: UiRect::all(Val::Px(5.0)),
+                            margin: UiRect {
+                                bottom: Val::Px(5.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        background_color: background.into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        let title = match unlocked {
+                            true => achievement.name.clone(),
+                            false => format!("{} (locked)", achievement.name),
+                        };
+
+                        parent.spawn(TextBundle::from_section(title, text_style(25.0f32, color)));
+                        parent.spawn(TextBundle::from_section(
+                            achievement.description.clone(),
+                            text_style(18.0f32, color),
+                        ));
+                    });
+            }
+
+            // Back button
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        size: Size::new(Val::Px(200.0), Val::Auto),
+                        padding: UiRect {
+                            top: Val::Px(10.0),
+                            bottom: Val::Px(7.0),
+                            ..default()
+                        },
+                        margin: UiRect {
+                            top: Val::Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.0, 0.15, 0.15).into(),
+                    ..default()
+                })
+                .insert(BackButton)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("Back", text_style(30.0f32, Color::WHITE)));
+                });
+        });
+}
+
+fn enter(
+    mut commands: Commands,
+    save: Res<Save>,
+    menu_assets: Res<MenuAssets>,
+    achievement_lists: Res<Assets<AchievementList>>,
+) {
+    info!("Entered achievements state");
+    let list = achievement_lists.get(&menu_assets.achievements);
+
+    spawn_text(&mut commands, &save, list, &menu_assets);
+}
+
+fn tick(
+    button_q: Query<&Interaction, (With<BackButton>, Changed<Interaction>)>,
+    mut commands: Commands,
+) {
+    if button_q.iter().any(|x| *x == Interaction::Clicked) {
+        commands.insert_resource(NextState(GameState::MainMenu));
+    }
+}
+
+fn exit(
+    mut commands: Commands,
+    elems_query: Query<Entity, Without<GameplayCamera>>,
+) {
+    info!("Exited achievements state");
+
+    for e in elems_query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub fn setup_states(app: &mut App, _params: &LaunchParams) {
+    app
+        .add_enter_system(GameState::Achievements, enter)
+        .add_system(tick.run_in_state(GameState::Achievements))
+        .add_exit_system(GameState::Achievements, exit);
+}
@@ -2,26 +2,83 @@ use bevy::prelude::*;
 use bevy_pkv::PkvStore;
 use iyes_loopless::prelude::*;
 
-use super::GameState;
+use std::time::Duration;
+
+use super::{ CurrentLevel, GameState, PauseState };
 
 use crate::states::main_menu::MenuAssets;
-use crate::save::Save;
+use crate::moveable::Side;
+use crate::save::{ LevelRecord, Save };
 use crate::level_info::LevelInfo;
 use crate::player::{ PlayerTag };
-use crate::tile::TileEvent;
+use crate::tile::{ LevelRules, TileEvent };
 use crate::{GameplayCamera, LaunchParams};
 
 #[derive(Resource)]
 struct LevelCompleteCountdown(Timer);
 
-fn enter() {
+/// Gives the player some time to rewind the fatal roll.
+#[derive(Resource)]
+struct DeathCountdown(Timer);
+
+/// The results of the current attempt.
+#[derive(Resource, Default)]
+struct LevelStats {
+    moves: u32,
+    time: Duration,
+}
+
+fn enter(mut commands: Commands) {
     info!("Entered ingame state");
+    commands.insert_resource(LevelStats::default());
 }
 
-fn death_system_normal(mut commands: Commands, player_q: Query<(), With<PlayerTag>>) {
-    if player_q.is_empty() {
-        info!("You are dead");
-        commands.insert_resource(NextState(GameState::MainMenu));
+fn stats_system(
+    mut stats: ResMut<LevelStats>,
+    timer: Option<Res<LevelCompleteCountdown>>,
+    time: Res<Time>,
+    mut save: Option<ResMut<Save>>,
+    player_q: Query<&Side, (With<PlayerTag>, Changed<Side>)>,
+) {
+    // The attempt is over once the level is beaten
+    if timer.is_some() { return; }
+
+    stats.time += time.delta();
+    for side in player_q.iter() {
+        if let Side::Changing { .. } = side {
+            stats.moves += 1;
+            if let Some(save) = save.as_mut() {
+                save.counters_mut().moves += 1;
+            }
+        }
+    }
+}
+
+fn death_system_normal(
+    mut commands: Commands,
+    mut timer: Option<ResMut<DeathCountdown>>,
+    mut save: ResMut<Save>,
+    time: Res<Time>,
+    player_q: Query<(), With<PlayerTag>>,
+) {
+    match (player_q.is_empty(), timer.as_mut()) {
+        (true, None) => {
+            info!("You are dead");
+            save.counters_mut().deaths += 1;
+            commands.insert_resource(DeathCountdown(Timer::from_seconds(1.5f32, TimerMode::Once)));
+        },
+        (true, Some(timer)) => {
+            timer.0.tick(time.delta());
+            if timer.0.finished() {
+                commands.remove_resource::<DeathCountdown>();
+                commands.insert_resource(NextState(GameState::MainMenu));
+            }
+        },
+        (false, Some(_)) => {
+            info!("You are alive again");
+            commands.remove_resource::<DeathCountdown>();
+        },
+        (false, None) => (),
     }
 }
 
@@ -50,6 +107,8 @@ fn level_complete_system_normal(
     mut commands: Commands,
     mut timer: Option<ResMut<LevelCompleteCountdown>>,
     mut save: ResMut<Save>,
+    stats: Res<LevelStats>,
+    current_level: Option<Res<CurrentLevel>>,
     menu_assets: Res<MenuAssets>,
     level_infos: Res<Assets<LevelInfo>>,
     time: Res<Time>,
@@ -60,14 +119,20 @@ fn level_complete_system_normal(
         if timer.0.finished() {
             let level_info = level_infos.get(&menu_assets.level_info).unwrap();
 
-            save.register_level_complete(level_info);
-
-            // TODO retry?
-            match pkv.set("save", &*save) {
-                Ok(()) => (),
-                Err(e) => error!("Error recording save: {}\nThe progress will be lost.", e),
+            match current_level {
+                Some(current) => save.register_level_complete(
+                    level_info,
+                    (current.world, current.level),
+                    LevelRecord {
+                        moves: stats.moves,
+                        time: stats.time.as_secs_f32(),
+                    },
+                ),
+                None => warn!("Don't know which level has been beaten"),
             }
 
+            save.store(&mut pkv);
+
             commands.remove_resource::<LevelCompleteCountdown>();
             commands.insert_resource(NextState(GameState::MainMenu));
         }
@@ -89,10 +154,22 @@ fn level_complete_system_testing_level(
 
 fn exit(
     mut commands: Commands,
+    save: Option<Res<Save>>,
+    mut pkv: ResMut<PkvStore>,
     mut cam: Query<&mut Transform, With<GameplayCamera>>,
     to_del: Query<Entity, Without<GameplayCamera>>,
 ) {
     info!("Exited ingame state");
+    commands.remove_resource::<LevelCompleteCountdown>();
+    commands.remove_resource::<DeathCountdown>();
+    commands.remove_resource::<LevelRules>();
+    commands.insert_resource(NextState(PauseState::Running));
+
+    // Keep the counters, even if the level hasn't been beaten
+    if let Some(save) = save {
+        save.store(&mut pkv);
+    }
+
     for mut tf in cam.iter_mut() { tf.translation = Vec3::new(0.0f32, 0.0f32, 50.0f32); }
 
     for e in to_del.iter() {
@@ -104,15 +181,16 @@ pub fn setup_states(app: &mut App, params: &LaunchParams) {
     app
         .add_enter_system(GameState::InGame, enter)
         .add_system(beat_system.run_in_state(GameState::InGame))
+        .add_system(stats_system.run_in_state(GameState::InGame).run_in_state(PauseState::Running))
         .add_exit_system(GameState::InGame, exit);
 
     if params.level_file.is_some() {
         app
-            .add_system(level_complete_system_testing_level.run_in_state(GameState::InGame))
-            .add_system(death_system_testing_level.run_in_state(GameState::InGame));
+            .add_system(level_complete_system_testing_level.run_in_state(GameState::InGame).run_in_state(PauseState::Running))
+            .add_system(death_system_testing_level.run_in_state(GameState::InGame).run_in_state(PauseState::Running));
     } else {
         app
-            .add_system(level_complete_system_normal.run_in_state(GameState::InGame))
-            .add_system(death_system_normal.run_in_state(GameState::InGame));
+            .add_system(level_complete_system_normal.run_in_state(GameState::InGame).run_in_state(PauseState::Running))
+            .add_system(death_system_normal.run_in_state(GameState::InGame).run_in_state(PauseState::Running));
     }
 }
@@ -0,0 +1,219 @@
+use bevy::prelude::*;
+use bevy_asset_loader::dynamic_asset::*;
+use iyes_loopless::prelude::*;
+
+use super::{ CurrentLevel, GameState, enter_level };
+use crate::states::main_menu::MenuAssets;
+use crate::save::Save;
+use crate::level_info::{ LevelInfo, level_path };
+use crate::{GameplayCamera, LaunchParams};
+
+#[derive(Clone, Copy, Component)]
+enum LevelSelectButton {
+    Level {
+        world: u8,
+        level: u8,
+    },
+    Back,
+}
+
+fn level_status(save: &Save, world: u8, level: u8) -> String {
+    if !save.is_unlocked(world, level) {
+        return "Locked".to_owned();
+    }
+
+    match save.record(world, level) {
+        Some(record) => format!("{} moves\n{:.1}s", record.moves, record.time),
+        None if save.is_complete(world, level) => "Complete".to_owned(),
+        None => "Not beaten".to_owned(),
+    }
+}
+
+fn spawn_text(
+    commands: &mut Commands,
+    save: &Save,
+    level_info: &LevelInfo,
+    menu_assets: &MenuAssets,
+) {
+    let font = menu_assets.main_font.clone();
+    let text_style = |font_size| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+
+    // Root node
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            // Header
+            parent
+                .spawn(TextBundle {
+                    style: Style {
+                        margin: UiRect {
+                            top: Val::Px(20.0),
+                            bottom: Val::Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    text: Text::from_section("Choose level", text_style(50.0f32))
+                        .with_alignment(TextAlignment::CENTER),
+                    ..default()
+                });
+
+            // One row per world
+            for world in 0..level_info.world_count() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            margin: UiRect {
+                                bottom: Val::Px(8.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        background_color: Color::NONE.into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent
+                            .spawn(TextBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(150.0), Val::Auto),
+                                    ..default()
+                                },
+                                text: Text::from_section(
+                                    format!("World {}", world + 1),
+                                    text_style(30.0f32),
+                                ),
+                                ..default()
+                            });
+
+                        for level in 0..level_info.level_count_in_world(world) {
+                            let color = if !save.is_unlocked(world, level) {
+                                Color::rgb(0.1, 0.1, 0.1)
+                            } else if save.is_complete(world, level) {
+                                Color::rgb(0.0, 0.3, 0.2)
+                            } else {
+                                Color::rgb(0.0, 0.15, 0.15)
+                            };
+
+                            let mut button = parent.spawn(ButtonBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    align_items: AlignItems::Center,
+                                    size: Size::new(Val::Px(150.0), Val::Px(80.0)),
+                                    margin: UiRect {
+                                        left: Val::Px(10.0),
+                                        ..default()
+                                    },
+                                    padding: UiRect::all(Val::Px(5.0)),
+                                    ..default()
+                                },
+                                background_color: color.into(),
+                                ..default()
+                            });
+
+                            if save.is_unlocked(world, level) {
+                                button.insert(LevelSelectButton::Level { world, level });
+                            }
+
+                            button.with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    format!("{}-{}", world + 1, level + 1),
+                                    text_style(30.0f32),
+                                ));
+                                parent.spawn(TextBundle::from_section(
+                                    level_status(save, world, level),
+                                    text_style(18.0f32),
+                                ));
+                            });
+                        }
+                    });
+            }
+
+            // Back button
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        size: Size::new(Val::Px(200.0), Val::Auto),
+                        padding: UiRect {
+                            top: Val::Px(10.0),
+                            bottom: Val::Px(7.0),
+                            ..default()
+                        },
+                        margin: UiRect {
+                            top: Val::Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.0, 0.15, 0.15).into(),
+                    ..default()
+                })
+                .insert(LevelSelectButton::Back)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("Back", text_style(30.0f32)));
+                });
+        });
+}
+
+fn enter(
+    mut commands: Commands,
+    save: Res<Save>,
+    menu_assets: Res<MenuAssets>,
+    level_infos: Res<Assets<LevelInfo>>,
+) {
+    info!("Entered level select state");
+    let level_info = level_infos.get(&menu_assets.level_info).unwrap();
+
+    spawn_text(&mut commands, &save, level_info, &menu_assets);
+}
+
+fn tick(
+    button_q: Query<(&Interaction, &LevelSelectButton), Changed<Interaction>>,
+    mut commands: Commands,
+    mut asset_keys: ResMut<DynamicAssets>,
+) {
+    for (interaction, button) in button_q.iter() {
+        if *interaction != Interaction::Clicked { continue; }
+        match *button {
+            LevelSelectButton::Level { world, level } => {
+                commands.insert_resource(CurrentLevel { world, level });
+                enter_level(level_path(world, level), &mut commands, &mut asset_keys);
+            },
+            LevelSelectButton::Back => commands.insert_resource(NextState(GameState::MainMenu)),
+        }
+    }
+}
+
+fn exit(
+    mut commands: Commands,
+    elems_query: Query<Entity, Without<GameplayCamera>>,
+) {
+    info!("Exited level select state");
+
+    for e in elems_query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub fn setup_states(app: &mut App, _params: &LaunchParams) {
+    app
+        .add_enter_system(GameState::LevelSelect, enter)
+        .add_system(tick.run_in_state(GameState::LevelSelect))
+        .add_exit_system(GameState::LevelSelect, exit);
+}
@@ -6,7 +6,7 @@ use super::{ GameState, jump_to_state };
 use bevy_tiled::tileset_indexing;
 use crate::LaunchParams;
 use crate::level::{ BaseLevelAssets, spawn_level, get_level_map };
-use crate::player::{ GeneratedPlayerAssets, BasePlayerAssets, spawn_player };
+use crate::player::{ GeneratedPlayerAssets, BasePlayerAssets, spawn_player, add_player_graphics };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LoadingLevel {
@@ -41,7 +41,7 @@ pub fn setup_states(app: &mut App, _params: &LaunchParams) {
     app.add_enter_system_set(
         LoadingLevel::PlayerEntity,
         SystemSet::new()
-            .with_system(spawn_player)
+            .with_system(spawn_player.pipe(add_player_graphics))
             .with_system(jump_to_state(LoadingLevel::Cleanup))
     );
 
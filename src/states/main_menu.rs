@@ -1,11 +1,12 @@
 use bevy::prelude::*;
 use bevy_pkv::PkvStore;
-use bevy_asset_loader::{ asset_collection::*, dynamic_asset::* };
+use bevy_asset_loader::asset_collection::*;
 use iyes_loopless::prelude::*;
 
-use super::{ GameState, enter_level };
+use super::{ GameState, SettingsMenu };
 use crate::save::Save;
 use crate::level_info::LevelInfo;
+use crate::achievement::AchievementList;
 use crate::{GameplayCamera, LaunchParams};
 
 #[derive(Resource, AssetCollection)]
@@ -14,6 +15,8 @@ pub struct MenuAssets {
     pub main_font: Handle<Font>,
     #[asset(path = "maps/level_info.level-info")]
     pub level_info: Handle<LevelInfo>,
+    #[asset(path = "achievements.achievements")]
+    pub achievements: Handle<AchievementList>,
 }
 
 
@@ -181,20 +184,13 @@ fn enter(
 fn tick(
     button_q: Query<(&Interaction, &MainMenuButton)>,
     mut commands: Commands,
-    save: Option<Res<Save>>,
-    mut asset_keys: ResMut<DynamicAssets>,
 ) {
     for (interaction, button) in button_q.iter() {
         if *interaction != Interaction::Clicked { break; }
         match button {
-            MainMenuButton::PickLevel => {
-                if let Some(save) = save.as_ref() {
-                    let (world, level) = save.world_level();
-                    enter_level(format!("maps/level{}-{}.tmx", world, level), &mut commands, &mut asset_keys);
-                }
-            },
-            MainMenuButton::Achievements => (),
-            MainMenuButton::Settings => (),
+            MainMenuButton::PickLevel => commands.insert_resource(NextState(GameState::LevelSelect)),
+            MainMenuButton::Achievements => commands.insert_resource(NextState(GameState::Achievements)),
+            MainMenuButton::Settings => commands.insert_resource(NextState(SettingsMenu::Open)),
             MainMenuButton::Quit => (),
         }
     }
@@ -1,7 +1,11 @@
+mod achievements;
 mod booting;
 mod ingame;
+mod level_select;
 mod main_menu;
 mod loading;
+mod pause;
+mod settings;
 mod splash_screen;
 
 use bevy::prelude::*;
@@ -9,6 +13,10 @@ use bevy_asset_loader::{ standard_dynamic_asset::*, dynamic_asset::* };
 use iyes_loopless::prelude::*;
 use loading::LoadingLevel;
 
+pub use main_menu::MenuAssets;
+pub use pause::PauseState;
+pub use settings::SettingsMenu;
+
 use crate::LaunchParams;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -21,6 +29,10 @@ pub enum GameState {
     SplashScreen,
     // The main menu
     MainMenu,
+    // Picking the level to play
+    LevelSelect,
+    // The list of achievements
+    Achievements,
     // Loading a level
     LoadingLevel,
     // The game
@@ -31,20 +43,40 @@ pub fn jump_to_state<T: bevy::ecs::schedule::StateData>(state: T) -> impl Fn(Com
     move |mut commands: Commands| commands.insert_resource(NextState(state.clone()))
 }
 
+/// The level being played. Isn't present when testing a level file.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentLevel {
+    pub world: u8,
+    pub level: u8,
+}
+
 pub fn enter_level(level_path: String, commands: &mut Commands, asset_keys: &mut DynamicAssets) {
     asset_keys.register_asset("map", Box::new(StandardDynamicAsset::File { path: level_path }));
     commands.insert_resource(NextState(GameState::LoadingLevel));
     commands.insert_resource(NextState(LoadingLevel::BaseAssets));
 }
 
+/// Respawns the current level from the already loaded map, skipping
+/// the loading of the base assets.
+pub fn restart_level(commands: &mut Commands) {
+    commands.insert_resource(NextState(GameState::LoadingLevel));
+    commands.insert_resource(NextState(LoadingLevel::LevelEntity));
+}
+
 pub fn setup_states(app: &mut App, params: &LaunchParams) {
     app.add_loopless_state(GameState::Booting);
     app.add_loopless_state(LoadingLevel::Done); 
+    app.add_loopless_state(PauseState::Running);
+    app.add_loopless_state(SettingsMenu::Closed);
 
     booting::setup_states(app, params);
     splash_screen::setup_states(app, params);
     main_menu::setup_states(app, params);
+    level_select::setup_states(app, params);
+    achievements::setup_states(app, params);
     loading::setup_states(app, params);
 
     ingame::setup_states(app, params);
+    pause::setup_states(app, params);
+    settings::setup_states(app, params);
 }
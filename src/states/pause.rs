@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use super::{ GameState, SettingsMenu, restart_level };
+use crate::action::{ Action, ActionInput };
+use crate::player::PlayerWinnerTag;
+use crate::LaunchParams;
+
+/// Whether the gameplay is running. Only matters during `GameState::InGame`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PauseState {
+    Running,
+    Paused,
+}
+
+#[derive(Clone, Copy, Component)]
+struct PauseOverlayTag;
+
+#[derive(Clone, Copy, Component)]
+enum PauseMenuButton {
+    Resume,
+    Restart,
+    MainMenu,
+    Settings,
+    Quit,
+}
+
+fn spawn_overlay(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    testing_level: bool,
+) {
+    // NOTE the menu assets aren't loaded when testing a level
+    let font: Handle<Font> = asset_server.load("fonts/plain.ttf");
+
+    commands
+        .spawn((
+            PauseOverlayTag,
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            // Header
+            parent
+                .spawn(TextBundle {
+                    style: Style {
+                        margin: UiRect {
+                            bottom: Val::Px(30.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    text: Text::from_section(
+                        "Paused",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 60.0f32,
+                            color: Color::WHITE,
+                        },
+                    ).with_alignment(TextAlignment::CENTER),
+                    ..default()
+                });
+
+            let mut spawn_button = |title, tag| {
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            size: Size::new(Val::Px(300.0), Val::Auto),
+                            padding: UiRect {
+                                top: Val::Px(13.0),
+                                bottom: Val::Px(10.0),
+                                ..default()
+                            },
+                            margin: UiRect {
+                                bottom: Val::Px(20.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.0, 0.15, 0.15).into(),
+                        ..default()
+                    })
+                    .insert(tag)
+                    .with_children(|parent| {
+                        parent
+                            .spawn(TextBundle {
+                                text: Text::from_section(
+                                    title,
+                                    TextStyle {
+                                        font: font.clone(),
+                                        font_size: 35.0f32,
+                                        color: Color::WHITE,
+                                    },
+                                ).with_alignment(TextAlignment::CENTER),
+                                ..default()
+                            });
+                    });
+            };
+
+            spawn_button("Resume", PauseMenuButton::Resume);
+            spawn_button("Restart level", PauseMenuButton::Restart);
+            if testing_level {
+                spawn_button("Quit", PauseMenuButton::Quit);
+            } else {
+                spawn_button("Main menu", PauseMenuButton::MainMenu);
+                spawn_button("Settings", PauseMenuButton::Settings);
+            }
+        });
+}
+
+fn exit(
+    mut commands: Commands,
+    overlay_q: Query<Entity, With<PauseOverlayTag>>,
+) {
+    for e in overlay_q.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+/// The pause action toggles the pause and the restart action restarts
+/// the level right away. Both are ignored once the level is beaten or
+/// while the settings are open.
+fn hotkeys(
+    mut commands: Commands,
+    actions: Res<ActionInput>,
+    pause: Res<CurrentState<PauseState>>,
+    winner_q: Query<(), With<PlayerWinnerTag>>,
+) {
+    if !winner_q.is_empty() { return; }
+
+    if actions.just_pressed(Action::Pause) {
+        match pause.0 {
+            PauseState::Running => commands.insert_resource(NextState(PauseState::Paused)),
+            PauseState::Paused => commands.insert_resource(NextState(PauseState::Running)),
+        }
+    }
+
+    if actions.just_pressed(Action::Restart) {
+        restart_level(&mut commands);
+    }
+}
+
+fn tick(
+    mut commands: Commands,
+    button_q: Query<(&Interaction, &PauseMenuButton), Changed<Interaction>>,
+    mut writer: EventWriter<bevy::app::AppExit>,
+) {
+    for (interaction, button) in button_q.iter() {
+        if *interaction != Interaction::Clicked { continue; }
+        match button {
+            PauseMenuButton::Resume => commands.insert_resource(NextState(PauseState::Running)),
+            PauseMenuButton::Restart => restart_level(&mut commands),
+            PauseMenuButton::MainMenu => commands.insert_resource(NextState(GameState::MainMenu)),
+            PauseMenuButton::Settings => commands.insert_resource(NextState(SettingsMenu::Open)),
+            PauseMenuButton::Quit => writer.send(bevy::app::AppExit),
+        }
+    }
+}
+
+pub fn setup_states(app: &mut App, params: &LaunchParams) {
+    let testing_level = params.level_file.is_some();
+
+    app
+        .add_enter_system(
+            PauseState::Paused,
+            move |mut commands: Commands, asset_server: Res<AssetServer>| {
+                info!("Paused");
+                spawn_overlay(&mut commands, &asset_server, testing_level);
+            },
+        )
+        .add_exit_system(PauseState::Paused, exit)
+        .add_system(
+            hotkeys
+                .run_in_state(GameState::InGame)
+                .run_in_state(SettingsMenu::Closed)
+        )
+        .add_system(
+            tick
+                .run_in_state(GameState::InGame)
+                .run_in_state(PauseState::Paused)
+        );
+}
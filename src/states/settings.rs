@@ -0,0 +1,412 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::action::{ Action, ActionInput, Binding, Bindings };
+use crate::settings::Settings;
+use crate::LaunchParams;
+
+const FRAME_LIMITS: [u32; 5] = [30, 60, 120, 144, 0];
+
+/// Whether the settings overlay is shown. The overlay can be opened on
+/// top of any other screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettingsMenu {
+    Closed,
+    Open,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SettingsField {
+    MasterVolume,
+    SfxVolume,
+    FrameLimit,
+    Vsync,
+    Fullscreen,
+    AnimationSpeed,
+}
+
+impl SettingsField {
+    const ALL: [SettingsField; 6] = [
+        SettingsField::MasterVolume,
+        SettingsField::SfxVolume,
+        SettingsField::FrameLimit,
+        SettingsField::Vsync,
+        SettingsField::Fullscreen,
+        SettingsField::AnimationSpeed,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            SettingsField::MasterVolume => "Master volume",
+            SettingsField::SfxVolume => "Effects volume",
+            SettingsField::FrameLimit => "Frame limit",
+            SettingsField::Vsync => "VSync",
+            SettingsField::Fullscreen => "Window mode",
+            SettingsField::AnimationSpeed => "Animation speed",
+        }
+    }
+
+    fn display(self, settings: &Settings) -> String {
+        let on_off = |x| if x { "On".to_owned() } else { "Off".to_owned() };
+
+        match self {
+            SettingsField::MasterVolume => format!("{:.0}%", settings.master_volume * 100.0f32),
+            SettingsField::SfxVolume => format!("{:.0}%", settings.sfx_volume * 100.0f32),
+            SettingsField::FrameLimit => match settings.frame_limit {
+                0 => "Off".to_owned(),
+                fps => format!("{fps}"),
+            },
+            SettingsField::Vsync => on_off(settings.vsync),
+            SettingsField::Fullscreen => match settings.fullscreen {
+                true => "Fullscreen".to_owned(),
+                false => "Windowed".to_owned(),
+            },
+            SettingsField::AnimationSpeed => format!("{:.2}x", settings.animation_speed),
+        }
+    }
+
+    fn step(self, settings: &mut Settings, forward: bool) {
+        let step = |x: f32, delta: f32, min: f32, max: f32| {
+            let x = if forward { x + delta } else { x - delta };
+            // Keep the values from drifting away from the steps
+            ((x * 100.0f32).round() / 100.0f32).clamp(min, max)
+        };
+
+        match self {
+            SettingsField::MasterVolume =>
+                settings.master_volume = step(settings.master_volume, 0.1f32, 0.0f32, 1.0f32),
+            SettingsField::SfxVolume =>
+                settings.sfx_volume = step(settings.sfx_volume, 0.1f32, 0.0f32, 1.0f32),
+            SettingsField::FrameLimit => {
+                let idx = FRAME_LIMITS.iter()
+                    .position(|x| *x == settings.frame_limit)
+                    .unwrap_or(1);
+                let idx = match forward {
+                    true => (idx + 1) % FRAME_LIMITS.len(),
+                    false => (idx + FRAME_LIMITS.len() - 1) % FRAME_LIMITS.len(),
+                };
+                settings.frame_limit = FRAME_LIMITS[idx];
+            },
+            SettingsField::Vsync => settings.vsync = !settings.vsync,
+            SettingsField::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            SettingsField::AnimationSpeed =>
+                settings.animation_speed = step(settings.animation_speed, 0.25f32, 0.5f32, 2.0f32),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Component)]
+struct SettingsOverlayTag;
+
+#[derive(Clone, Copy, Component)]
+struct SettingsValueText(SettingsField);
+
+#[derive(Clone, Copy, Component)]
+struct BindingText(Action);
+
+#[derive(Clone, Copy, Component)]
+enum SettingsButton {
+    Step {
+        field: SettingsField,
+        forward: bool,
+    },
+    /// Waits for the next key or gamepad button to bind to the action
+    Rebind(Action),
+    Close,
+}
+
+/// The action waiting for a new binding.
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+fn describe_bindings(bindings: &Bindings, action: Action) -> String {
+    bindings.of(action)
+        .map(|binding| match binding {
+            Binding::Key(key) => format!("{key:?}"),
+            Binding::GamepadButton(button) => format!("{button:?}"),
+            Binding::GamepadStick { axis, positive: true } => format!("{axis:?}+"),
+            Binding::GamepadStick { axis, positive: false } => format!("{axis:?}-"),
+        })
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+fn spawn_overlay(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    settings: &Settings,
+) {
+    // NOTE the menu assets aren't loaded when testing a level
+    let font: Handle<Font> = asset_server.load("fonts/plain.ttf");
+    let text_style = |font_size| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+    let button_bundle = |width| ButtonBundle {
+        style: Style {
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            size: Size::new(Val::Px(width), Val::Px(40.0)),
+            margin: UiRect::all(Val::Px(5.0)),
+            ..default()
+        },
+        background_color: Color::rgb(0.0, 0.15, 0.15).into(),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            SettingsOverlayTag,
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgb(0.05, 0.1, 0.1).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            // Header
+            parent
+                .spawn(TextBundle {
+                    style: Style {
+                        margin: UiRect {
+                            bottom: Val::Px(20.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    text: Text::from_section("Settings", text_style(60.0f32)),
+                    ..default()
+                });
+
+            parent
+                .spawn(NodeBundle {
+                    background_color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    // General settings
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                margin: UiRect::horizontal(Val::Px(20.0)),
+                                ..default()
+                            },
+                            background_color: Color::NONE.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for field in SettingsField::ALL {
+                                parent
+                                    .spawn(NodeBundle {
+                                        style: Style {
+                                            align_items: AlignItems::Center,
+                                            ..default()
+                                        },
+                                        background_color: Color::NONE.into(),
+                                        ..default()
+                                    })
+                                    .with_children(|parent| {
+                                        parent.spawn(TextBundle {
+                                            style: Style {
+                                                size: Size::new(Val::Px(220.0), Val::Auto),
+                                                ..default()
+                                            },
+                                            text: Text::from_section(field.title(), text_style(30.0f32)),
+                                            ..default()
+                                        });
+
+                                        parent
+                                            .spawn(button_bundle(40.0))
+                                            .insert(SettingsButton::Step { field, forward: false })
+                                            .with_children(|parent| {
+                                                parent.spawn(TextBundle::from_section("<", text_style(30.0f32)));
+                                            });
+
+                                        parent
+                                            .spawn(NodeBundle {
+                                                style: Style {
+                                                    justify_content: JustifyContent::Center,
+                                                    size: Size::new(Val::Px(150.0), Val::Auto),
+                                                    ..default()
+                                                },
+                                                background_color: Color::NONE.into(),
+                                                ..default()
+                                            })
+                                            .with_children(|parent| {
+                                                parent.spawn((
+                                                    SettingsValueText(field),
+                                                    TextBundle::from_section(
+                                                        field.display(settings),
+                                                        text_style(30.0f32),
+                                                    ),
+                                                ));
+                                            });
+
+                                        parent
+                                            .spawn(button_bundle(40.0))
+                                            .insert(SettingsButton::Step { field, forward: true })
+                                            .with_children(|parent| {
+                                                parent.spawn(TextBundle::from_section(">", text_style(30.0f32)));
+                                            });
+                                    });
+                            }
+                        });
+
+                    // Controls
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                margin: UiRect::horizontal(Val::Px(20.0)),
+                                ..default()
+                            },
+                            background_color: Color::NONE.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for action in Action::ALL {
+                                parent
+                                    .spawn(NodeBundle {
+                                        style: Style {
+                                            align_items: AlignItems::Center,
+                                            ..default()
+                                        },
+                                        background_color: Color::NONE.into(),
+                                        ..default()
+                                    })
+                                    .with_children(|parent| {
+                                        parent.spawn(TextBundle {
+                                            style: Style {
+                                                size: Size::new(Val::Px(160.0), Val::Auto),
+                                                ..default()
+                                            },
+                                            text: Text::from_section(action.title(), text_style(25.0f32)),
+                                            ..default()
+                                        });
+
+                                        parent
+                                            .spawn(button_bundle(300.0))
+                                            .insert(SettingsButton::Rebind(action))
+                                            .with_children(|parent| {
+                                                parent.spawn((
+                                                    BindingText(action),
+                                                    TextBundle::from_section(
+                                                        describe_bindings(&settings.bindings, action),
+                                                        text_style(18.0f32),
+                                                    ),
+                                                ));
+                                            });
+                                    });
+                            }
+                        });
+                });
+
+            parent
+                .spawn(button_bundle(200.0))
+                .insert(SettingsButton::Close)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("Back", text_style(30.0f32)));
+                });
+        });
+}
+
+fn enter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+) {
+    info!("Opened settings");
+    spawn_overlay(&mut commands, &asset_server, &settings);
+}
+
+fn tick(
+    mut commands: Commands,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    actions: Res<ActionInput>,
+    key_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    button_q: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+) {
+    if let Some(action) = rebinding.0 {
+        let binding = key_input.get_just_pressed()
+            .map(|key| Binding::Key(*key))
+            .chain(gamepad_input.get_just_pressed().map(|x| Binding::GamepadButton(x.button_type)))
+            .next();
+
+        match binding {
+            // Escape cancels the rebinding
+            Some(Binding::Key(KeyCode::Escape)) => rebinding.0 = None,
+            Some(binding) => {
+                settings.bindings.rebind(action, binding);
+                rebinding.0 = None;
+            },
+            None => (),
+        }
+        return;
+    }
+
+    if actions.just_pressed(Action::Pause) {
+        commands.insert_resource(NextState(SettingsMenu::Closed));
+    }
+
+    for (interaction, button) in button_q.iter() {
+        if *interaction != Interaction::Clicked { continue; }
+        match *button {
+            SettingsButton::Step { field, forward } => field.step(&mut settings, forward),
+            SettingsButton::Rebind(action) => rebinding.0 = Some(action),
+            SettingsButton::Close => commands.insert_resource(NextState(SettingsMenu::Closed)),
+        }
+    }
+}
+
+fn update_values(
+    settings: Res<Settings>,
+    rebinding: Res<Rebinding>,
+    mut value_q: Query<(&mut Text, &SettingsValueText), Without<BindingText>>,
+    mut binding_q: Query<(&mut Text, &BindingText), Without<SettingsValueText>>,
+) {
+    if !settings.is_changed() && !rebinding.is_changed() { return; }
+
+    for (mut text, value) in value_q.iter_mut() {
+        text.sections[0].value = value.0.display(&settings);
+    }
+
+    for (mut text, binding) in binding_q.iter_mut() {
+        text.sections[0].value = match rebinding.0 {
+            Some(action) if action == binding.0 => "Press a key or a button...".to_owned(),
+            _ => describe_bindings(&settings.bindings, binding.0),
+        };
+    }
+}
+
+fn exit(
+    mut commands: Commands,
+    overlay_q: Query<Entity, With<SettingsOverlayTag>>,
+) {
+    info!("Closed settings");
+    commands.insert_resource(Rebinding::default());
+
+    for e in overlay_q.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub fn setup_states(app: &mut App, _params: &LaunchParams) {
+    app
+        .init_resource::<Rebinding>()
+        .add_enter_system(SettingsMenu::Open, enter)
+        .add_system(tick.run_in_state(SettingsMenu::Open))
+        .add_system(update_values.run_in_state(SettingsMenu::Open))
+        .add_exit_system(SettingsMenu::Open, exit);
+}
@@ -0,0 +1,267 @@
+//! A harness for testing the gameplay systems without a window. It plays
+//! a [HeadlessLevel] built from an ASCII grid or loaded from a map file,
+//! with the controls and the replays on top.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::bail;
+use bevy::app::AppExit;
+use bevy::ecs::event::Events;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use cube_rot::MoveDirection;
+use iyes_loopless::prelude::*;
+use puzzle_rules::{Flip, Layout, LogicKind, Pos, SideCondition};
+
+use crate::action::{Action, ActionPlugin, Binding};
+use crate::headless::{HeadlessLevel, SETTLE_LIMIT};
+use crate::player::add_player_controls;
+use crate::replay::{Replay, ReplayPlayback, ReplayPlugin};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::replay::ReplayRecording;
+use crate::settings::Settings;
+use crate::states::GameState;
+
+pub use crate::headless::FRAME;
+pub use crate::moveable::Side;
+pub use crate::tile::TileEvent;
+
+/// Parses a level from a grid of whitespace separated cells. The first
+/// line is the top row, so just like in the game `y` grows upwards.
+///
+/// Each cell is a tile kind followed by any amount of triggers:
+///
+/// * `-` -- no tile
+/// * `.` -- floor, `S` -- start, `E` -- exit, `F` -- frier
+/// * `R` and `L` -- clock-wise and counter-clock-wise spinners
+/// * `C^`, `C<`, `Cv` and `C>` -- conveyors facing up, left, down and right
+/// * `B<id>` -- a once button with the ID
+/// * `+o` and `+e` -- odd and even side triggers
+/// * `+<id>` -- a trigger for the button with the ID
+///
+/// For example, `S B1 E+1` is a level where the button opens the exit.
+pub fn parse_ascii(grid: &str) -> anyhow::Result<Layout> {
+    let rows: Vec<_> = grid.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let mut layout = Layout::new();
+    let mut triggers = Vec::new();
+
+    for (row, line) in rows.iter().enumerate() {
+        let y = (rows.len() - row - 1) as u32;
+
+        for (x, cell) in line.split_whitespace().enumerate() {
+            let pos = Pos::new(x as u32, y);
+            let mut parts = cell.split('+');
+            let tile = parts.next().unwrap_or_default();
+
+            let (kind, flip) = match tile {
+                "-" => continue,
+                "." => (LogicKind::Floor, Flip::default()),
+                "S" => (LogicKind::Start, Flip::default()),
+                "E" => (LogicKind::Exit, Flip::default()),
+                "F" => (LogicKind::Frier, Flip::default()),
+                "R" => (LogicKind::Spinner, spinner_flip(true)),
+                "L" => (LogicKind::Spinner, spinner_flip(false)),
+                "C^" => (LogicKind::Conveyor, conveyor_flip(MoveDirection::Up)),
+                "C<" => (LogicKind::Conveyor, conveyor_flip(MoveDirection::Left)),
+                "Cv" => (LogicKind::Conveyor, conveyor_flip(MoveDirection::Down)),
+                "C>" => (LogicKind::Conveyor, conveyor_flip(MoveDirection::Right)),
+                _ => match tile.strip_prefix('B').map(str::parse) {
+                    Some(Ok(id)) => (LogicKind::OnceButton(id), Flip::default()),
+                    _ => bail!("Unknown tile {tile:?} at {x}:{y}"),
+                },
+            };
+            layout.add_tile(pos, kind, flip);
+            triggers.extend(parts.map(|trigger| (pos, trigger)));
+        }
+    }
+
+    for (pos, trigger) in triggers {
+        match trigger {
+            "o" => layout.add_side_trigger(pos, SideCondition::OnOddSide),
+            "e" => layout.add_side_trigger(pos, SideCondition::OnEvenSide),
+            _ => match trigger.parse() {
+                Ok(id) => layout.add_button_trigger(pos, id),
+                Err(_) => bail!("Unknown trigger {trigger:?} at {}:{}", pos.x, pos.y),
+            },
+        };
+    }
+
+    Ok(layout)
+}
+
+fn all_flips() -> impl Iterator<Item = Flip> {
+    (0..8u8).map(|bits| Flip {
+        x: bits & 1 != 0,
+        y: bits & 2 != 0,
+        d: bits & 4 != 0,
+    })
+}
+
+fn conveyor_flip(dir: MoveDirection) -> Flip {
+    all_flips().find(|flip| flip.direction() == dir).unwrap()
+}
+
+fn spinner_flip(clock_wise: bool) -> Flip {
+    all_flips().find(|flip| flip.is_clock_wise() == clock_wise).unwrap()
+}
+
+/// The tile events sent so far, for [TestLevel::take_tile_events].
+#[derive(Resource, Default)]
+struct TileEventLog(Vec<TileEvent>);
+
+fn log_tile_events(mut events: EventReader<TileEvent>, mut log: ResMut<TileEventLog>) {
+    log.0.extend(events.iter().copied());
+}
+
+/// A [HeadlessLevel] with the controls, the replays and the tile events
+/// at hand.
+pub struct TestLevel {
+    level: HeadlessLevel,
+}
+
+impl TestLevel {
+    pub fn new(layout: &Layout) -> anyhow::Result<Self> {
+        Ok(Self::wrap(HeadlessLevel::new(layout)?))
+    }
+
+    pub fn from_ascii(grid: &str) -> anyhow::Result<Self> {
+        Self::new(&parse_ascii(grid)?)
+    }
+
+    /// See [HeadlessLevel::from_tmx].
+    pub fn from_tmx(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self::wrap(HeadlessLevel::from_tmx(path)?))
+    }
+
+    fn wrap(mut level: HeadlessLevel) -> Self {
+        level.app()
+            .init_resource::<TileEventLog>()
+            .add_system_to_stage(CoreStage::Last, log_tile_events);
+
+        TestLevel { level }
+    }
+
+    /// Makes the level take the rolls as key presses, which go through
+    /// the bindings and the player controls like in the game, so that the
+    /// attempt can be recorded and played back.
+    pub fn with_controls(mut self) -> Self {
+        let app = self.level.app();
+        app
+            .add_plugin(InputPlugin)
+            .add_plugin(ActionPlugin)
+            .add_loopless_state(GameState::InGame)
+            .add_plugin(ReplayPlugin);
+        add_player_controls(app);
+
+        self
+    }
+
+    /// Records the attempt into `dir`. Needs [TestLevel::with_controls].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn recording_into(mut self, dir: &Path) -> Self {
+        self.level.app().insert_resource(ReplayRecording { dir: dir.to_owned() });
+
+        self
+    }
+
+    pub fn app(&mut self) -> &mut App {
+        self.level.app()
+    }
+
+    /// See [HeadlessLevel::step].
+    pub fn step(&mut self, dt: Duration) {
+        self.level.step(dt);
+    }
+
+    /// See [HeadlessLevel::roll].
+    pub fn roll(&mut self, dir: MoveDirection) -> bool {
+        self.level.roll(dir)
+    }
+
+    /// See [HeadlessLevel::settle].
+    pub fn settle(&mut self) -> bool {
+        self.level.settle()
+    }
+
+    /// Holds the key of the roll for a frame. Needs
+    /// [TestLevel::with_controls].
+    pub fn press(&mut self, dir: MoveDirection) {
+        let app = self.level.app();
+        let key = app.world.resource::<Settings>().bindings
+            .of(Action::from_move_direction(dir))
+            .find_map(|binding| match binding {
+                Binding::Key(key) => Some(key),
+                _ => None,
+            })
+            .expect("The roll has no key");
+
+        app.world.resource_mut::<Input<KeyCode>>().press(key);
+        self.level.step(FRAME);
+        self.level.app().world.resource_mut::<Input<KeyCode>>().release(key);
+    }
+
+    /// Closes the app the way the window would, which writes the recorded
+    /// attempt.
+    pub fn exit(&mut self) {
+        self.level.app().world.resource_mut::<Events<AppExit>>().send(AppExit);
+        self.level.step(FRAME);
+    }
+
+    /// Plays the replay back, until it's over and the level has settled.
+    /// Returns `false` if that hasn't happened in 20 seconds. Needs
+    /// [TestLevel::with_controls].
+    pub fn play_back(&mut self, replay: Replay) -> bool {
+        self.level.app().insert_resource(ReplayPlayback::new(replay));
+        let mut elapsed = Duration::ZERO;
+
+        loop {
+            if elapsed >= SETTLE_LIMIT { return false; }
+
+            self.level.step(FRAME);
+            elapsed += FRAME;
+
+            if self.level.app().world.resource::<ReplayPlayback>().is_finished()
+                && self.level.is_settled()
+            {
+                return true;
+            }
+        }
+    }
+
+    /// Rolls the player and lets the level settle after each roll. Returns
+    /// `false` as soon as a roll gets denied or the level doesn't settle.
+    pub fn play(&mut self, moves: &[MoveDirection]) -> bool {
+        moves.iter().all(|dir| self.level.roll(*dir) && self.level.settle())
+    }
+
+    /// See [HeadlessLevel::player_pos].
+    pub fn player_pos(&mut self) -> Option<Pos> {
+        self.level.player_pos()
+    }
+
+    pub fn player_side(&self) -> Option<Side> {
+        self.level.player_side()
+    }
+
+    pub fn is_player_alive(&self) -> bool {
+        self.level.is_player_alive()
+    }
+
+    pub fn has_won(&self) -> bool {
+        self.level.has_won()
+    }
+
+    /// See [HeadlessLevel::tile_state].
+    pub fn tile_state(&mut self, pos: Pos) -> Option<bool> {
+        self.level.tile_state(pos)
+    }
+
+    /// Takes all the tile events sent since the last call.
+    pub fn take_tile_events(&mut self) -> Vec<TileEvent> {
+        std::mem::take(&mut self.level.app().world.resource_mut::<TileEventLog>().0)
+    }
+}
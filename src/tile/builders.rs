@@ -0,0 +1,71 @@
+//! The builders of the tile families this module adds. They get picked
+//! by the tileset when a level is spawned.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_tiled::{TileBuilder, TileBuilderAppExt, TilesetData};
+
+use crate::moveable::MoveableTilemapTag;
+use super::{LogicTileBundle, LogicTilemapTag, TriggerTile, TriggerTilemapTag};
+
+pub fn register_tile_builders(app: &mut App) {
+    app
+        .register_tileset_builder::<LogicTileBundle>("logic_tiles", |cmds| {
+            cmds.insert((
+                LogicTilemapTag,
+                MoveableTilemapTag,
+                Visibility { is_visible: false },
+            ));
+        })
+        .register_tileset_builder_with("activator_tiles", || Box::new(TriggerTileBuilder::default()));
+}
+
+/// Builds the trigger tiles. Unlike [bevy_tiled::BasicDeserBuilder] it inserts a
+/// different bundle depending on the class of the tile.
+#[derive(Default)]
+struct TriggerTileBuilder {
+    deserialized_props: HashMap<usize, Arc<HashMap<u32, TriggerTile>>>,
+}
+
+impl TileBuilder for TriggerTileBuilder {
+    fn process_tileset(
+        &mut self,
+        set_id: usize,
+        tileset: &TilesetData,
+    ) -> anyhow::Result<()> {
+        self.deserialized_props.insert(set_id, tileset.properties()?);
+
+        Ok(())
+    }
+
+    fn build(
+        &mut self,
+        set_id: usize,
+        id: u32,
+        cmds: &mut EntityCommands,
+    ) -> anyhow::Result<()> {
+        match self.deserialized_props.get(&set_id).and_then(|props| props.get(&id)) {
+            Some(TriggerTile::TriggerTileBundle(bundle)) => { cmds.insert(bundle.clone()); },
+            Some(TriggerTile::ButtonTriggerTileBundle(bundle)) => { cmds.insert(bundle.clone()); },
+            None => anyhow::bail!("Tile {} didn't have any deserialized properties", id),
+        }
+
+        Ok(())
+    }
+
+    fn finish_layer(
+        &mut self,
+        _set_id: usize,
+        cmds: &mut EntityCommands,
+    ) -> anyhow::Result<()> {
+        cmds.insert((
+            TriggerTilemapTag,
+            Visibility { is_visible: false },
+        ));
+
+        Ok(())
+    }
+}
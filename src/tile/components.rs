@@ -2,11 +2,16 @@ use bevy::{ecs::query::WorldQuery, prelude::*};
 use bevy_ecs_tilemap::tiles::TileFlip;
 use bevy_ecs_tilemap_cpu_anim::CPUTileAnimation;
 use cube_rot::MoveDirection;
+use puzzle_rules::Flip;
 use serde::Deserialize;
 
+// The tiles are read straight into the types of the rules
+pub use puzzle_rules::{LogicKind, SideCondition};
+
 /// Describes a trigger that will activate when a button activates
-#[derive(Clone, Copy, Debug, Component, Deserialize)]
+#[derive(Clone, Copy, Debug, Component, Deserialize, Reflect)]
 #[repr(transparent)]
+#[serde(transparent)]
 pub struct ButtonCondition(pub u8);
 
 impl ButtonCondition {
@@ -15,24 +20,6 @@ impl ButtonCondition {
     }
 }
 
-/// Describes a trigger that will activate based off player's side
-#[derive(Clone, Copy, Debug, Component, Deserialize, Reflect)]
-pub enum SideCondition {
-    /// The tile is expecting an odd number to be player's uppser side
-    OnOddSide,
-    /// The tile is expecting an even number to be player's upper side
-    OnEvenSide,
-}
-
-impl SideCondition {
-    pub fn is_active(self, upper_side: u8) -> bool {
-        match self {
-            SideCondition::OnOddSide => upper_side % 2 == 1,
-            SideCondition::OnEvenSide => upper_side % 2 == 0,
-        }
-    }
-}
-
 /// Describes how the tile should be animated, based off its state.
 #[derive(Reflect, Debug, Default, Clone, Component)]
 pub struct GraphicsAnimating {
@@ -46,60 +33,35 @@ pub struct GraphicsAnimating {
 #[derive(Clone, Copy, Default, Debug, Component, Reflect, PartialEq, Eq)]
 pub struct LogicState(pub bool);
 
-/// The tile kind. This data type is mapped directly to the ones you can see in the
-/// level editor.
-///
-/// The tile kind dictates what graphics the game should use and how should the tile
-/// behave.
-#[derive(
-    Clone,
-    Copy,
-    Default,
-    Debug,
-    Component,
-    Reflect,
-    PartialEq,
-    Eq,
-    Hash,
-    Deserialize,
-)]
-#[repr(u16)]
-pub enum LogicKind {
-    /// Once button tiles are activated when interacted with by a player and stay activated
-    /// for the rest of the level.
-    OnceButton(u8),
-    /// Conveyor tiles push any moveable into the direction they are facing towards
-    /// when they are active.
-    Conveyor,
-    /// Start tiles are pretty much floor tiles. The only thing that makes them special
-    /// is that the player spawns on them.
-    Start,
-    /// Frier tiles destroy any moveable that steps on them when they are active.
-    Frier,
-    /// Spinner tiles spin the moveable, that stepped on them, around when they are active.
-    Spinner,
-    /// Exit tiles announce that the level has been beated when the player-tagged moveable
-    /// has stepped on them.
-    Exit,
-    /// Floor tiles do nothing
-    #[default]
-    Floor,
-}
-
 /// A bundle to quickly construct a logical tile.
-#[derive(Clone, Default, Bundle, Deserialize)]
+#[derive(Clone, Default, Bundle, Deserialize, Reflect)]
 pub struct LogicTileBundle {
     pub ty: LogicKind,
     #[serde(skip)]
+    #[reflect(ignore)]
     pub state: LogicState,
 }
 
 /// A bundle to quickly construct a trigger tile.
-#[derive(Clone, Bundle, Deserialize)]
+#[derive(Clone, Bundle, Deserialize, Reflect)]
 pub struct TriggerTileBundle {
     pub active: SideCondition,
 }
 
+/// A bundle to quickly construct a button trigger tile.
+#[derive(Clone, Bundle, Deserialize, Reflect)]
+pub struct ButtonTriggerTileBundle {
+    pub button: ButtonCondition,
+}
+
+/// Any tile from the trigger tileset. The variant is picked by the
+/// class of the tile.
+#[derive(Clone, Deserialize)]
+pub enum TriggerTile {
+    TriggerTileBundle(TriggerTileBundle),
+    ButtonTriggerTileBundle(ButtonTriggerTileBundle),
+}
+
 /// A bundle to quickly construct a graphics tile.
 #[derive(Clone, Bundle)]
 pub struct GraphicsTileBundle {
@@ -116,18 +78,19 @@ pub struct LogicTileQuery {
 }
 
 impl<'a> LogicTileQueryItem<'a> {
+    /// Returns the tile's flipping flags in the rules' format.
+    pub fn rules_flip(&self) -> Flip {
+        Flip { x: self.flip.x, y: self.flip.y, d: self.flip.d }
+    }
+
     /// Returns the direction towards which the tiles is facing.
     pub fn direction(&self) -> MoveDirection {
-        MoveDirection::Up.apply_flipping_flags(
-            self.flip.x,
-            self.flip.y,
-            self.flip.d,
-        )
+        self.rules_flip().direction()
     }
 
     /// Tells whether the tile is clock-wise or counter-clock-wise oriented.
     pub fn is_clock_wise(&self) -> bool {
-        !(self.flip.x ^ self.flip.y ^ self.flip.d)
+        self.rules_flip().is_clock_wise()
     }
 
     /// Tells whether the tile is active or not.
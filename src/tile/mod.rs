@@ -1,15 +1,21 @@
+mod builders;
 mod components;
+mod resources;
 mod systems;
 mod events;
 
 use bevy::prelude::*;
 use bevy_ecs_tilemap_cpu_anim::CPUTileAnimationPlugin;
+use iyes_loopless::prelude::*;
 
 pub use components::*;
 pub use events::*;
+pub use resources::*;
 pub use systems::*;
 
+use builders::register_tile_builders;
 use crate::moveable::MoveableUpdateStage;
+use crate::states::PauseState;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, StageLabel)]
 pub struct TileUpdateStage;
@@ -18,9 +24,7 @@ pub struct TilePlugin;
 
 #[derive(Clone, Copy, SystemLabel)]
 enum TileSystem {
-    TileUpdate,
-    SideTrigger,
-    ButtonTrigger,
+    Outcomes,
     AnimationSwitch,
 }
 
@@ -42,29 +46,22 @@ impl Plugin for TilePlugin {
                 TileUpdateStage,
                 SystemSet::new()
                     .with_system(
-                        handle_player_side_triggers
-                            .label(TileSystem::SideTrigger),
-                    )
-                    .with_system(
-                        handle_button_triggers
-                            .label(TileSystem::ButtonTrigger),
-                    )
-                    .with_system(
-                        special_tile_handler
-                            .label(TileSystem::TileUpdate)
-                            .after(TileSystem::SideTrigger)
-                            .after(TileSystem::ButtonTrigger),
+                        play_outcomes
+                            .run_in_state(PauseState::Running)
+                            .run_if_resource_exists::<LevelRules>()
+                            .label(TileSystem::Outcomes),
                     )
                     .with_system(
                         tile_state_animation_switch
                             .label(TileSystem::AnimationSwitch)
-                            .after(TileSystem::SideTrigger)
-                            .after(TileSystem::ButtonTrigger),
+                            .after(TileSystem::Outcomes),
                     )
                     .with_system(
                         tile_transition_anim_switch
                             .after(TileSystem::AnimationSwitch),
                     ),
             );
+
+        register_tile_builders(app);
     }
 }
@@ -0,0 +1,88 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::tiles::{TilePos, TileFlip};
+use cube_rot::MoveDirection;
+use puzzle_rules::{Flip, Layout, LevelError, LevelState, Outcome, Pos};
+
+use super::{ButtonCondition, LogicKind, SideCondition};
+
+/// The rules of the level being played. Every roll of the player gets
+/// resolved by the rules at once, then the tile systems play the outcomes
+/// back, one animation at a time.
+#[derive(Resource, Clone, Debug)]
+pub struct LevelRules {
+    state: LevelState,
+    pending: VecDeque<Outcome>,
+}
+
+impl LevelRules {
+    pub fn new(state: LevelState) -> Self {
+        LevelRules {
+            state,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Collects the rules from the spawned logic and trigger tiles. The
+    /// player starts on the first start tile.
+    pub fn from_tiles<'a>(
+        logic_tiles: impl IntoIterator<Item = (&'a TilePos, &'a LogicKind, &'a TileFlip)>,
+        side_triggers: impl IntoIterator<Item = (&'a TilePos, &'a SideCondition)>,
+        button_triggers: impl IntoIterator<Item = (&'a TilePos, &'a ButtonCondition)>,
+    ) -> Result<Self, LevelError> {
+        let pos = |TilePos { x, y }: &TilePos| Pos::new(*x, *y);
+        let mut layout = Layout::new();
+
+        for (at, kind, flip) in logic_tiles {
+            layout.add_tile(pos(at), *kind, Flip { x: flip.x, y: flip.y, d: flip.d });
+        }
+        // Triggers can only be attached once all the logic tiles are known
+        for (at, cond) in side_triggers {
+            layout.add_side_trigger(pos(at), *cond);
+        }
+        for (at, cond) in button_triggers {
+            layout.add_button_trigger(pos(at), cond.0);
+        }
+
+        Ok(LevelRules::new(LevelState::new(Arc::new(layout))?))
+    }
+
+    pub fn state(&self) -> &LevelState {
+        &self.state
+    }
+
+    /// Rolls the player and queues up the outcomes for the tile systems.
+    /// Returns `false` if the roll has been denied, either because there
+    /// is no tile to roll onto, the level is over or the outcomes of the
+    /// previous roll haven't been played yet.
+    pub fn roll(&mut self, dir: MoveDirection) -> bool {
+        if !self.is_settled() { return false; }
+
+        // Denied rolls leave the state as it is
+        let outcomes = self.state.step(dir);
+        if outcomes.is_empty() || outcomes == [Outcome::Blocked] {
+            return false;
+        }
+        self.pending.extend(outcomes);
+
+        true
+    }
+
+    /// Tells whether all the outcomes have been played back.
+    pub fn is_settled(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Puts the level back into a state, dropping the outcomes which
+    /// haven't been played back.
+    pub fn restore(&mut self, state: LevelState) {
+        self.state = state;
+        self.pending.clear();
+    }
+
+    pub(super) fn next_outcome(&mut self) -> Option<Outcome> {
+        self.pending.pop_front()
+    }
+}
@@ -1,11 +1,12 @@
 use super::*;
 use crate::moveable::*;
 use crate::player::{PlayerTag, PlayerWinnerTag};
-use anyhow::Context;
 use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
 use bevy_ecs_tilemap::tiles::{TilePos, TileStorage};
 use bevy_ecs_tilemap_cpu_anim::CPUAnimated;
+use puzzle_rules::{Outcome, Pos};
+use std::time::Duration;
 
 /// Switched the animation for tiles that have finished their
 /// transition animation
@@ -78,148 +79,78 @@ fn switch_animation(
     }
 }
 
-pub fn handle_button_triggers(
-    mut tile_events: EventReader<TileEvent>,
-    trigger_q: Query<(&ButtonCondition, &TilePos)>,
+/// How long the player takes to roll onto the next tile.
+pub const ROLL_TIME: Duration = Duration::from_millis(520);
+/// How long a conveyor takes to push a moveable onto the next tile.
+pub const SLIDE_TIME: Duration = Duration::from_millis(500);
+/// How long a spinner takes to spin a moveable around.
+pub const SPIN_TIME: Duration = Duration::from_millis(500);
+
+/// Plays back the outcomes of the player's rolls. The moves get animated
+/// one after another, while the rest of the outcomes are applied as soon
+/// as the moves before them are over.
+pub fn play_outcomes(
+    mut commands: Commands,
+    mut rules: ResMut<LevelRules>,
+    mut tile_events: EventWriter<TileEvent>,
     logic_tilemap_q: Query<&TileStorage, With<LogicTilemapTag>>,
     mut logic_tile_q: Query<&mut LogicState>,
+    mut player_q: Query<(Entity, MoveableQuery), With<PlayerTag>>,
 ) {
-    let logic_tilemap = match logic_tilemap_q.get_single() {
+    let (player_id, mut player) = match player_q.get_single_mut() {
         Ok(x) => x,
-        _ => return,
-    };
-
-    let mut apply_button_trigger = |pos, cond, button_id, entity| {
-        match logic_tile_q.get_mut(entity) {
-            Ok(mut tile_state) => apply_button_trigger(button_id, cond, &mut tile_state),
-            Err(e) => error!("Stale logic tile for {pos:?}: {e}"),
-        }
-    };
-
-    tile_events.iter()
-        .filter_map(|ev| match ev {
-            TileEvent::ButtonPressed { button_id } => Some(*button_id),
-            _ => None,
-        })
-        .for_each(|button_id| {
-            trigger_q.iter()
-                .filter_map(|(cond, pos)| logic_tilemap.get(pos).map(|entity| (pos, cond, entity)))
-                .for_each(|(pos, cond, entity)| apply_button_trigger(pos, cond, button_id, entity))
-        })
-}
-
-fn apply_button_trigger(
-    button_id: u8,
-    cond: &ButtonCondition,
-    tile_state: &mut LogicState,
-) {
-    if cond.is_active(button_id) && *tile_state != LogicState(true) {
-        *tile_state = LogicState(true);
-    }
-}
-
-/// Switches tile state, depending on which number the player has on their upper side.
-/// This system makes sure to trigger `Changed` only when the state of a tile actually changes.
-pub fn handle_player_side_triggers(
-    player_q: Query<&Side, (With<PlayerTag>, Changed<Side>)>,
-    trigger_q: Query<(&SideCondition, &TilePos)>,
-    logic_tilemap_q: Query<&TileStorage, With<LogicTilemapTag>>,
-    mut logic_tile_q: Query<&mut LogicState>,
-) {
-    let (player_side, logic_tilemap) = match (player_q.get_single(), logic_tilemap_q.get_single()) {
-        (Ok(x), Ok(y)) => (x, y),
-        _ => return,
-    };
-
-    // Redefine the function locally for easier usage
-    let mut apply_side_trigger = |pos, cond, entity| {
-        match logic_tile_q.get_mut(entity) {
-            Ok(mut tile_state) => apply_side_trigger(player_side, cond, &mut tile_state),
-            Err(e) => error!("Stale logic tile for {pos:?}: {e}"),
-        }
+        Err(_) => return,
     };
 
-    trigger_q.iter()
-        .filter_map(|(cond, pos)| logic_tilemap.get(pos).map(|entity| (pos, cond, entity)))
-        .for_each(|(pos, cond, entity)| apply_side_trigger(pos, cond, entity))
-}
-
-fn apply_side_trigger(
-    player_side: &Side,
-    cond: &SideCondition,
-    tile_state: &mut LogicState,
-) {
-    if let Side::Changing { from, to } = player_side {
-        let next_state = cond.is_active(*to);
+    while !player.is_moving() {
+        let outcome = match rules.next_outcome() {
+            Some(x) => x,
+            None => return,
+        };
 
-        // Do transition only when the state changes
-        if cond.is_active(*from) != next_state {
-            *tile_state = LogicState(next_state);
+        match outcome {
+            Outcome::Rolled { dir, .. } => { player.flip(dir, ROLL_TIME); },
+            Outcome::Slid { dir, .. } => { player.slide(dir, SLIDE_TIME); },
+            Outcome::Spun { clock_wise } => player.rotate(clock_wise, SPIN_TIME),
+            Outcome::TileChanged { pos, active } => set_tile_state(
+                &logic_tilemap_q,
+                &mut logic_tile_q,
+                pos,
+                active,
+            ),
+            Outcome::ButtonPressed { button_id } => {
+                tile_events.send(TileEvent::ButtonPressed { button_id });
+            },
+            Outcome::ExitReached => {
+                commands
+                    .entity(player_id)
+                    .remove::<MoveableBundle>()
+                    .insert(PlayerWinnerTag::new());
+                tile_events.send(TileEvent::ExitReached);
+                return;
+            },
+            Outcome::Died => {
+                commands.entity(player_id).despawn();
+                return;
+            },
+            Outcome::Looped => info!("The player is stuck on the conveyors"),
+            Outcome::Blocked => (),
         }
     }
 }
 
-pub fn special_tile_handler(
-    mut interactions: EventReader<TileInteractionEvent>,
-    mut tile_events: EventWriter<TileEvent>,
-    mut tile_query: Query<LogicTileQuery>,
-    mut move_query: Query<(MoveableQuery, Option<&PlayerTag>)>,
-    mut commands: Commands,
+fn set_tile_state(
+    logic_tilemap_q: &Query<&TileStorage, With<LogicTilemapTag>>,
+    logic_tile_q: &mut Query<&mut LogicState>,
+    Pos { x, y }: Pos,
+    active: bool,
 ) {
-    let mut try_handle_interaction = |interaction: &TileInteractionEvent| {
-        let (moveable, player_tag) = move_query.get_mut(interaction.moveable_id).context("Fetching moveable")?;
-        let tile = tile_query.get_mut(interaction.tile_id).context("Fetching tile")?;
+    let pos = TilePos { x, y };
+    let entity = logic_tilemap_q.iter().find_map(|storage| storage.get(&pos));
 
-        handle_interaction(
-            &mut commands,
-            &mut tile_events,
-            tile,
-            moveable,
-            player_tag.is_some(),
-            interaction.moveable_id,
-        );
-
-        anyhow::Ok(())
-    };
-
-    for interaction in interactions.iter() {
-        if let Err(e) = try_handle_interaction(interaction) {
-            error!("Error while handling interaction: {e:?}");
-        }
+    match entity.map(|entity| logic_tile_q.get_mut(entity)) {
+        Some(Ok(mut state)) => *state = LogicState(active),
+        Some(Err(e)) => error!("Stale logic tile for {pos:?}: {e}"),
+        None => error!("No logic tile at {pos:?}"),
     }
 }
-
-fn handle_interaction(
-    commands: &mut Commands,
-    tile_events: &mut EventWriter<TileEvent>,
-    mut tile: LogicTileQueryItem,
-    mut moveable: MoveableQueryItem,
-    is_moveable_player: bool,
-    moveable_id: Entity,
-) {
-    use std::time::Duration;
-
-    match &tile.kind {
-        LogicKind::OnceButton(button_id) => if is_moveable_player && !tile.is_active() {
-            *tile.state = LogicState(true);
-            tile_events.send(TileEvent::ButtonPressed { button_id: *button_id });
-        },
-        LogicKind::Conveyor => if tile.is_active() {
-            moveable.slide(tile.direction(), Duration::from_millis(500));
-        },
-        LogicKind::Frier => if tile.is_active() {
-            commands.entity(moveable_id).despawn();
-        },
-        LogicKind::Spinner => if tile.is_active() {
-            moveable.rotate(tile.is_clock_wise(), Duration::from_millis(500));
-        },
-        LogicKind::Exit => if is_moveable_player && tile.is_active() {
-            commands
-                .entity(moveable_id)
-                .remove::<MoveableBundle>()
-                .insert(PlayerWinnerTag::new());
-            tile_events.send(TileEvent::ExitReached);
-        },
-        _ => (),
-    }
-}
\ No newline at end of file
@@ -0,0 +1,35 @@
+//! Fixtures shared by the tests.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+
+use bevy_tiled::tiled;
+
+/// Serves the files from memory and the rest from the shipped tilesets.
+/// The files are matched by their names, because `tiled` joins the paths
+/// of the external tilesets without resolving the `..`.
+pub struct FixtureReader(HashMap<String, String>);
+
+impl FixtureReader {
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        FixtureReader(files.iter()
+            .map(|(name, data)| (name.to_string(), data.to_string()))
+            .collect())
+    }
+}
+
+impl tiled::ResourceReader for FixtureReader {
+    type Resource = Cursor<Vec<u8>>;
+    type Error = std::io::Error;
+
+    fn read_from(&mut self, path: &Path) -> Result<Self::Resource, Self::Error> {
+        let name = path.file_name().and_then(|x| x.to_str()).unwrap_or_default();
+
+        match self.0.get(name) {
+            Some(data) => Ok(Cursor::new(data.clone().into_bytes())),
+            None => std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/tilesets").join(name))
+                .map(Cursor::new),
+        }
+    }
+}
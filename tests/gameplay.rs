@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use cube_rot::MoveDirection::*;
+use game_lib::testing::{Side, TestLevel, TileEvent, FRAME};
+use game_lib::{PlayOutcome, Replay};
+use puzzle_rules::{solve, LevelState, Pos, Solution, Status};
+
+#[test]
+fn rolls_change_position_and_side() {
+    let mut level = TestLevel::from_ascii("
+        .  -
+        S  .
+    ").unwrap();
+
+    assert_eq!(level.player_side(), Some(Side::Ready(4)));
+
+    assert!(level.play(&[Right]));
+    assert_eq!(level.player_pos(), Some(Pos::new(1, 0)));
+    assert_eq!(level.player_side(), Some(Side::Ready(1)));
+
+    assert!(level.play(&[Left, Up]));
+    assert_eq!(level.player_pos(), Some(Pos::new(0, 1)));
+    assert_eq!(level.player_side(), Some(Side::Ready(5)));
+}
+
+#[test]
+fn rolls_into_the_void_are_cancelled() {
+    let mut level = TestLevel::from_ascii("- S").unwrap();
+
+    assert!(!level.roll(Left));
+    assert!(level.settle());
+    assert_eq!(level.player_pos(), Some(Pos::new(1, 0)));
+    assert_eq!(level.player_side(), Some(Side::Ready(4)));
+}
+
+#[test]
+fn side_changes_mid_roll() {
+    let mut level = TestLevel::from_ascii("S .").unwrap();
+
+    assert!(level.roll(Right));
+    level.step(FRAME);
+    assert_eq!(level.player_side(), Some(Side::Changing { from: 4, to: 1 }));
+    assert!(!level.roll(Left));
+}
+
+#[test]
+fn conveyor_pushes_when_active() {
+    let mut level = TestLevel::from_ascii("S C>+o .").unwrap();
+
+    assert!(level.play(&[Right]));
+    assert_eq!(level.tile_state(Pos::new(1, 0)), Some(true));
+    assert_eq!(level.player_pos(), Some(Pos::new(2, 0)));
+}
+
+#[test]
+fn conveyor_does_nothing_when_inactive() {
+    let mut level = TestLevel::from_ascii("S C>+e .").unwrap();
+
+    assert!(level.play(&[Right]));
+    assert_eq!(level.tile_state(Pos::new(1, 0)), Some(false));
+    assert_eq!(level.player_pos(), Some(Pos::new(1, 0)));
+}
+
+#[test]
+fn frier_destroys_the_player() {
+    let mut level = TestLevel::from_ascii("S F+o").unwrap();
+
+    assert!(level.play(&[Right]));
+    assert!(!level.is_player_alive());
+    assert_eq!(level.player_pos(), None);
+}
+
+#[test]
+fn spinner_turns_the_player() {
+    let mut spun = TestLevel::from_ascii("
+        -  .
+        S  R+o
+    ").unwrap();
+    let mut not_spun = TestLevel::from_ascii("
+        -  .
+        S  R
+    ").unwrap();
+
+    assert!(spun.play(&[Right, Up]));
+    assert!(not_spun.play(&[Right, Up]));
+    assert_eq!(spun.player_side(), Some(Side::Ready(4)));
+    assert_eq!(not_spun.player_side(), Some(Side::Ready(5)));
+}
+
+#[test]
+fn button_activates_its_triggers() {
+    let mut level = TestLevel::from_ascii("S B1 F+1 F+2").unwrap();
+
+    assert!(level.play(&[Right]));
+    assert_eq!(level.take_tile_events(), vec![TileEvent::ButtonPressed { button_id: 1 }]);
+    assert_eq!(level.tile_state(Pos::new(1, 0)), Some(true));
+    assert_eq!(level.tile_state(Pos::new(2, 0)), Some(true));
+    assert_eq!(level.tile_state(Pos::new(3, 0)), Some(false));
+
+    // Once buttons can't be pressed twice
+    assert!(level.play(&[Left, Right]));
+    assert!(level.take_tile_events().is_empty());
+}
+
+#[test]
+fn exit_finishes_the_level_when_active() {
+    let mut level = TestLevel::from_ascii("S B1 E+1").unwrap();
+
+    assert!(level.play(&[Right, Right]));
+    assert!(level.has_won());
+    assert_eq!(
+        level.take_tile_events(),
+        vec![TileEvent::ButtonPressed { button_id: 1 }, TileEvent::ExitReached],
+    );
+}
+
+#[test]
+fn inactive_exit_is_floor() {
+    let mut level = TestLevel::from_ascii("S . E").unwrap();
+
+    assert!(level.play(&[Right, Right]));
+    assert!(!level.has_won());
+    assert_eq!(level.player_pos(), Some(Pos::new(2, 0)));
+}
+
+/// The par of every shipped map
+fn par_table() -> BTreeMap<String, usize> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/maps/pars.json");
+
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn maps_match_the_par_table() {
+    let maps = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/maps");
+    let mut pars = BTreeMap::new();
+    let mut unsolvable = Vec::new();
+
+    for entry in std::fs::read_dir(maps).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+        if !name.starts_with("level") || !name.ends_with(".tmx") { continue; }
+
+        let state = game_lib::load_level_state(&path).unwrap();
+        match solve(&state).par() {
+            Some(par) => { pars.insert(name, par); },
+            None => unsolvable.push(name),
+        }
+    }
+
+    unsolvable.sort();
+    assert!(unsolvable.is_empty(), "The shipped maps can't be won: {unsolvable:?}");
+    assert_eq!(pars, par_table());
+}
+
+/// Checks that the spawned level agrees with the rules model.
+fn assert_matches_rules(level: &mut TestLevel, rules: &LevelState, name: &str) {
+    assert_eq!(level.player_pos(), Some(rules.player().pos), "{name}");
+    assert_eq!(
+        level.player_side(),
+        Some(Side::Ready(rules.player().upper_side())),
+        "{name}",
+    );
+    for (tile, active) in rules.layout().tiles().iter().zip(rules.tile_states()) {
+        assert_eq!(level.tile_state(tile.pos), Some(*active), "{name}: {:?}", tile.pos);
+    }
+}
+
+/// Spawns every map of the par table with the game's loader and checks it
+/// against the rules model. Then plays the shortest solution, checking the
+/// game after every roll.
+#[test]
+fn maps_play_like_the_rules_model() {
+    let maps = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/maps");
+
+    for (name, par) in par_table() {
+        let path = maps.join(&name);
+        let mut rules = game_lib::load_level_state(&path).unwrap();
+        let mut level = TestLevel::from_tmx(&path).unwrap();
+        assert_matches_rules(&mut level, &rules, &name);
+
+        let moves = match solve(&rules) {
+            Solution::Found(moves) if moves.len() == par => moves,
+            solution => panic!(
+                "{name}: the par table has {par}, but the solver has found {:?}",
+                solution.par(),
+            ),
+        };
+
+        for dir in moves {
+            rules.step(dir);
+            assert!(level.play(&[dir]), "{name}: the roll {dir:?} has failed");
+
+            if rules.status() == Status::Won { break; }
+            assert_matches_rules(&mut level, &rules, &name);
+        }
+
+        assert!(level.has_won(), "{name}: the solution doesn't win");
+    }
+}
+
+#[test]
+fn headless_runs_report_the_outcome() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/maps/level0-0.tmx");
+
+    let outcome = game_lib::play_headless(&path, &[]).unwrap();
+    assert_eq!(outcome, PlayOutcome::OutOfMoves);
+    assert_eq!(outcome.exit_code(), 5);
+
+    let moves = match solve(&game_lib::load_level_state(&path).unwrap()) {
+        Solution::Found(moves) => moves,
+        Solution::Unsolvable => panic!("{path:?} can't be won"),
+    };
+    let outcome = game_lib::play_headless(&path, &moves).unwrap();
+    assert_eq!(outcome, PlayOutcome::Won);
+    assert_eq!(outcome.exit_code(), 0);
+}
+
+#[test]
+fn replays_play_back_like_they_were_recorded() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/maps/level0-0.tmx");
+    let dir = std::env::temp_dir().join(format!("replays-{}", std::process::id()));
+
+    let mut recorded = TestLevel::from_tmx(&path).unwrap()
+        .with_controls()
+        .recording_into(&dir);
+    for roll in [Right, Up, Left, Down, Down, Right] {
+        recorded.press(roll);
+        assert!(recorded.settle());
+    }
+    recorded.exit();
+
+    let files: Vec<_> = std::fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(files.len(), 1, "{files:?}");
+    let replay = Replay::load(&files[0]).unwrap();
+    assert_eq!(replay.map, "maps/level0-0.tmx");
+    assert!(!replay.inputs.is_empty());
+
+    let mut played = TestLevel::from_tmx(&path).unwrap().with_controls();
+    assert!(played.play_back(replay));
+    assert_eq!(played.player_pos(), recorded.player_pos());
+    assert_eq!(played.player_side(), recorded.player_side());
+    assert_eq!(played.has_won(), recorded.has_won());
+}
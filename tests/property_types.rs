@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use game_lib::{Member, PropertyType};
+
+/// The editor has to offer the same classes and enum values the game
+/// deserializes. Run `native-launcher property-types` to fix the drift.
+#[test]
+fn tiled_project_matches_the_tile_types() {
+    let project = Path::new(env!("CARGO_MANIFEST_DIR")).join("levels.tiled-project");
+    let drift = game_lib::check_project_file(&project).unwrap();
+
+    assert!(drift.is_empty(), "{drift:#?}");
+}
+
+/// Every tile of the shipped tilesets has to deserialize into the type the
+/// game reads it as.
+#[test]
+fn shipped_tilesets_deserialize() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/tilesets");
+    let mut checked = 0;
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|x| x.to_str()) != Some("tsx") { continue; }
+
+        let drift = game_lib::check_tileset_file(&path).unwrap();
+        assert!(drift.is_empty(), "{path:?}: {drift:#?}");
+        checked += 1;
+    }
+
+    assert_eq!(checked, 3);
+}
+
+/// Variants with data can't be enum values, the editor stores them as a
+/// class named after the variant.
+#[test]
+fn data_variants_become_classes() {
+    let types = game_lib::generate_property_types().unwrap();
+    let find = |name: &str| types.iter().find(|x| x.name() == name).unwrap();
+
+    match find("LogicKind") {
+        PropertyType::Enum { values, .. } => assert!(!values.contains(&"OnceButton".to_owned())),
+        x => panic!("LogicKind should be an enum, found {x:?}"),
+    }
+    match find("OnceButton") {
+        PropertyType::Class { members, .. } => assert_eq!(
+            members.as_slice(),
+            [Member { name: "0".to_owned(), ty: "int", property_type: None }],
+        ),
+        x => panic!("OnceButton should be a class, found {x:?}"),
+    }
+}
@@ -0,0 +1,245 @@
+mod common;
+
+use std::path::Path;
+
+use bevy_tiled::tiled;
+use common::FixtureReader;
+use game_lib::{validate_map, validate_map_file, Diagnostic, Severity};
+
+/// A logic tileset with a tile the game can't read.
+const BROKEN_TILESET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.8" name="logic_tiles" tilewidth="32" tileheight="32" tilecount="1" columns="0">
+ <grid orientation="orthogonal" width="1" height="1"/>
+ <tile id="0" type="LogicTileBundle">
+  <properties>
+   <property name="ty" propertytype="LogicKind" value="Lava"/>
+  </properties>
+  <image width="32" height="32" source="../tiles/logic_floor.png"/>
+ </tile>
+</tileset>
+"#;
+
+// The IDs of the tiles, with the shipped tilesets
+const CONVEYOR: u32 = 1;
+const EXIT: u32 = 2;
+const FLOOR: u32 = 3;
+const START: u32 = 6;
+const BUTTON: u32 = 7;
+const ODD: u32 = 9;
+const BUTTON_TRIGGER: u32 = 10;
+
+fn layer(id: u32, name: &str, width: usize, rows: &[&[u32]]) -> String {
+    let data = rows.iter()
+        .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    format!(
+        r#" <layer id="{id}" name="{name}" width="{width}" height="{}">
+  <data encoding="csv">
+{data}
+</data>
+ </layer>
+"#,
+        rows.len(),
+    )
+}
+
+/// Makes a map out of a logic and a trigger layer. The first row is the
+/// top one, just like in the editor. An empty trigger layer is left out,
+/// so that it isn't reported.
+fn map_with(logic_tileset: &str, logic: &[&[u32]], triggers: &[&[u32]]) -> String {
+    let width = logic[0].len();
+    let triggers = match triggers.iter().any(|row| row.iter().any(|&x| x != 0)) {
+        true => layer(2, "triggers", width, triggers),
+        false => String::new(),
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.8" orientation="orthogonal" renderorder="right-down" width="{width}" height="{}" tilewidth="32" tileheight="32" infinite="0" nextlayerid="3" nextobjectid="1">
+ <tileset firstgid="1" source="../tilesets/{logic_tileset}"/>
+ <tileset firstgid="8" source="../tilesets/activator_tiles.tsx"/>
+{}{}</map>
+"#,
+        logic.len(),
+        layer(1, "logic", width, logic),
+        triggers,
+    )
+}
+
+fn validate(logic: &[&[u32]], triggers: &[&[u32]]) -> Vec<Diagnostic> {
+    validate_fixture(map_with("logic_tiles.tsx", logic, triggers))
+}
+
+fn validate_fixture(map: String) -> Vec<Diagnostic> {
+    let reader = FixtureReader::new(&[
+        ("fixture.tmx", &map),
+        ("broken_tiles.tsx", BROKEN_TILESET),
+    ]);
+    let map = tiled::Loader::with_reader(reader)
+        .load_tmx_map("maps/fixture.tmx")
+        .unwrap();
+
+    validate_map("fixture.tmx", &map)
+}
+
+/// The codes and the tiles of the diagnostics.
+fn codes(diagnostics: &[Diagnostic]) -> Vec<(&'static str, Option<(i32, i32)>)> {
+    diagnostics.iter().map(|x| (x.code, x.tile)).collect()
+}
+
+#[test]
+fn valid_level_has_no_diagnostics() {
+    let diagnostics = validate(
+        &[&[START, FLOOR, EXIT]],
+        &[&[0, 0, ODD]],
+    );
+
+    assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+}
+
+#[test]
+fn level_needs_a_start() {
+    let diagnostics = validate(&[&[FLOOR, EXIT]], &[&[0, ODD]]);
+
+    assert_eq!(codes(&diagnostics), [("no-start", None)]);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+}
+
+#[test]
+fn every_start_is_reported() {
+    let diagnostics = validate(
+        &[
+            &[START, EXIT],
+            &[FLOOR, START],
+        ],
+        &[&[0, ODD], &[0, 0]],
+    );
+
+    assert_eq!(
+        codes(&diagnostics),
+        [("multiple-starts", Some((0, 0))), ("multiple-starts", Some((1, 1)))],
+    );
+}
+
+#[test]
+fn level_needs_an_exit() {
+    let diagnostics = validate(&[&[START, CONVEYOR]], &[&[0, 0]]);
+
+    assert_eq!(codes(&diagnostics), [("no-exit", None)]);
+}
+
+#[test]
+fn buttons_need_a_trigger() {
+    let diagnostics = validate(&[&[START, BUTTON, EXIT]], &[&[0, 0, ODD]]);
+
+    assert_eq!(codes(&diagnostics), [("unmatched-button", Some((1, 0)))]);
+    assert!(diagnostics[0].message.contains("button 1"), "{}", diagnostics[0].message);
+}
+
+#[test]
+fn exits_need_a_trigger() {
+    let diagnostics = validate(
+        &[
+            &[START, EXIT],
+            &[FLOOR, EXIT],
+        ],
+        &[&[0, 0], &[0, ODD]],
+    );
+
+    assert_eq!(codes(&diagnostics), [("inactive-exit", Some((1, 0)))]);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+}
+
+#[test]
+fn buttons_can_activate_the_exit() {
+    let diagnostics = validate(
+        &[&[START, BUTTON, EXIT]],
+        &[&[0, 0, BUTTON_TRIGGER]],
+    );
+
+    assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+}
+
+#[test]
+fn triggers_need_a_logic_tile() {
+    let diagnostics = validate(
+        &[&[START, EXIT, 0]],
+        &[&[0, ODD, ODD]],
+    );
+
+    assert_eq!(codes(&diagnostics), [("dangling-trigger", Some((2, 0)))]);
+    assert_eq!(diagnostics[0].layer.as_deref(), Some("triggers"));
+}
+
+#[test]
+fn unreadable_tiles_are_reported() {
+    let diagnostics = validate_fixture(map_with(
+        "broken_tiles.tsx",
+        &[&[0, 1]],
+        &[&[0, 0]],
+    ));
+
+    // The broken tile is the only logic tile, so the level has no start
+    // and no exit either
+    assert_eq!(
+        codes(&diagnostics),
+        [("bad-tile", Some((1, 0))), ("no-start", None), ("no-exit", None)],
+    );
+    assert_eq!(diagnostics[0].layer.as_deref(), Some("logic"));
+}
+
+/// Adds an object layer with `objects` on top of the map.
+fn with_objects(map: String, objects: &str) -> String {
+    map.replace(
+        "</map>",
+        &format!(" <objectgroup id=\"3\" name=\"objects\">\n{objects} </objectgroup>\n</map>"),
+    )
+}
+
+#[test]
+fn level_objects_are_accepted() {
+    let map = map_with("logic_tiles.tsx", &[&[START, FLOOR, EXIT]], &[&[0, 0, ODD]]);
+    let diagnostics = validate_fixture(with_objects(map, r#"  <object id="1" name="note" x="16" y="16">
+   <point/>
+  </object>
+  <object id="2" name="hint" type="Hint" x="32" y="0">
+   <properties>
+    <property name="text" value="Roll onto the exit"/>
+   </properties>
+   <point/>
+  </object>
+"#));
+
+    assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+}
+
+#[test]
+fn unreadable_objects_are_reported() {
+    let map = map_with("logic_tiles.tsx", &[&[START, FLOOR, EXIT]], &[&[0, 0, ODD]]);
+    let diagnostics = validate_fixture(with_objects(map, r#"  <object id="1" name="portal" type="Portal" x="16" y="16">
+   <point/>
+  </object>
+"#));
+
+    assert_eq!(codes(&diagnostics), [("bad-object", None)]);
+    assert_eq!(diagnostics[0].layer.as_deref(), Some("objects"));
+    assert!(diagnostics[0].message.contains("\"portal\""), "{}", diagnostics[0].message);
+}
+
+#[test]
+fn shipped_maps_are_valid() {
+    let maps = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/maps");
+    let mut diagnostics = Vec::new();
+
+    for entry in std::fs::read_dir(maps).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        if !name.starts_with("level") || !name.ends_with(".tmx") { continue; }
+
+        diagnostics.extend(validate_map_file(&path));
+    }
+
+    assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+}